@@ -0,0 +1,164 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use futures::{Stream, StreamExt};
+use simple_payments_engine::{OperationRequest, PaymentsEngine};
+use simple_payments_engine::use_case::generate_workload_use_case::{write_workload, WorkloadSettings};
+use simple_payments_engine::use_case::operation_input::{process_operation_requests, RowRejection};
+use simple_payments_engine::use_case::process_from_csv_use_case::{read_operation_requests_from_csv, read_operation_requests_from_csv_bytes};
+use simple_payments_engine::use_case::processing_session::ProcessingSession;
+
+/*
+  Throughput is reported by criterion in rows per second ("elem/s"), peak memory is printed after each benchmark but validation.
+  The workload is generated in memory with the default settings of generate_workload; its size comes from BENCHMARK_ROWS (default 100000).
+    - parsing: CSV bytes to OperationRequests
+    - validation: validating every OperationRequest against the engine state right before it; only the validation is timed
+    - processing: validating and applying the already parsed OperationRequests to a new engine
+    - end_to_end: CSV bytes to the final accounts, like the binary does
+  parsing and end_to_end are measured with csv_async, like stdin is read, and with the fast reader, like files are read.
+*/
+const BENCHMARK_ROWS_VARIABLE: &str = "BENCHMARK_ROWS";
+
+/*
+  Tracks the bytes currently allocated and the peak since the last reset, on top of the system allocator.
+*/
+struct PeakMemoryAllocator;
+
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakMemoryAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = unsafe { System.alloc(layout) };
+        if !pointer.is_null() {
+            record_allocation(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(pointer, layout) };
+        ALLOCATED_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_pointer = unsafe { System.realloc(pointer, layout, new_size) };
+        if !new_pointer.is_null() {
+            ALLOCATED_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            record_allocation(new_size);
+        }
+        new_pointer
+    }
+}
+
+fn record_allocation(size: usize) {
+    let allocated_bytes = ALLOCATED_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_ALLOCATED_BYTES.fetch_max(allocated_bytes, Ordering::Relaxed);
+}
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: PeakMemoryAllocator = PeakMemoryAllocator;
+
+/*
+  Runs the routine once more, outside of the measurements, and prints how much memory it needed on top of what was already allocated.
+*/
+fn print_peak_memory<T>(benchmark_name: &str, routine: impl FnOnce() -> T) {
+    let baseline_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    PEAK_ALLOCATED_BYTES.store(baseline_bytes, Ordering::Relaxed);
+    black_box(routine());
+    let peak_bytes = PEAK_ALLOCATED_BYTES.load(Ordering::Relaxed) - baseline_bytes;
+    println!("{benchmark_name}: peak memory {:.1} MiB", peak_bytes as f64 / (1024.0 * 1024.0));
+}
+
+// Leaked, so that the readers can borrow it without copying it at every iteration
+fn generate_workload() -> &'static [u8] {
+    let rows = std::env::var(BENCHMARK_ROWS_VARIABLE).ok().and_then(|rows| rows.parse().ok()).unwrap_or(100_000);
+    let mut workload = vec![];
+    write_workload(&mut workload, &WorkloadSettings { rows, ..WorkloadSettings::default() }).unwrap();
+    workload.leak()
+}
+
+fn csv_async_rows(workload: &'static [u8]) -> impl Stream<Item = (u64, Result<OperationRequest, RowRejection>)> {
+    read_operation_requests_from_csv(futures::io::Cursor::new(workload))
+}
+
+fn fast_reader_rows(workload: &'static [u8]) -> impl Stream<Item = (u64, Result<OperationRequest, RowRejection>)> {
+    read_operation_requests_from_csv_bytes(workload, futures::io::Cursor::new(workload))
+}
+
+fn parse(runtime: &tokio::runtime::Runtime, rows: impl Stream<Item = (u64, Result<OperationRequest, RowRejection>)>) -> Vec<OperationRequest> {
+    runtime.block_on(async {
+        rows
+            .filter_map(|(_, operation_request_result)| async move { operation_request_result.ok() })
+            .collect()
+            .await
+    })
+}
+
+fn process(operation_requests: Vec<OperationRequest>) -> PaymentsEngine {
+    let mut payments_engine = PaymentsEngine::new();
+    for operation_request in operation_requests {
+        let _ = payments_engine.process(operation_request);
+    }
+    payments_engine
+}
+
+/*
+  Every operation is validated against the state right before it, like the engine does when processing it, and then processed
+  to get to the next state. Only the validations are timed, reading the clock around each of them included.
+*/
+fn time_validation(operation_requests: Vec<OperationRequest>) -> Duration {
+    let mut payments_engine = PaymentsEngine::new();
+    let mut validation_duration = Duration::ZERO;
+    for operation_request in operation_requests {
+        let validation_start = Instant::now();
+        let _ = black_box(payments_engine.validate(&operation_request));
+        validation_duration += validation_start.elapsed();
+        let _ = payments_engine.process(operation_request);
+    }
+    validation_duration
+}
+
+fn process_end_to_end(runtime: &tokio::runtime::Runtime, rows: impl Stream<Item = (u64, Result<OperationRequest, RowRejection>)>) -> PaymentsEngine {
+    runtime.block_on(async {
+        let mut processing_session = ProcessingSession::new(PaymentsEngine::new());
+        process_operation_requests(rows, "benchmark", &mut processing_session).await;
+        processing_session.finish().unwrap().0
+    })
+}
+
+fn payments_engine_benchmark(criterion: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let workload = generate_workload();
+    let operation_requests = parse(&runtime, csv_async_rows(workload));
+    let rows = operation_requests.len() as u64;
+
+    let mut benchmark_group = criterion.benchmark_group("payments_engine");
+    benchmark_group.throughput(Throughput::Elements(rows));
+    benchmark_group.sample_size(10);
+
+    benchmark_group.bench_function("parsing", |bencher| bencher.iter(|| parse(&runtime, csv_async_rows(workload))));
+    print_peak_memory("parsing", || parse(&runtime, csv_async_rows(workload)));
+
+    benchmark_group.bench_function("parsing_fast_reader", |bencher| bencher.iter(|| parse(&runtime, fast_reader_rows(workload))));
+    print_peak_memory("parsing_fast_reader", || parse(&runtime, fast_reader_rows(workload)));
+
+    benchmark_group.bench_function("validation", |bencher| bencher.iter_custom(|iterations| (0..iterations).map(|_| time_validation(operation_requests.clone())).sum()));
+
+    benchmark_group.bench_function("processing", |bencher| bencher.iter_batched(|| operation_requests.clone(), process, BatchSize::LargeInput));
+    let processed_operation_requests = operation_requests.clone();
+    print_peak_memory("processing", || process(processed_operation_requests));
+
+    benchmark_group.bench_function("end_to_end", |bencher| bencher.iter(|| process_end_to_end(&runtime, csv_async_rows(workload))));
+    print_peak_memory("end_to_end", || process_end_to_end(&runtime, csv_async_rows(workload)));
+
+    benchmark_group.bench_function("end_to_end_fast_reader", |bencher| bencher.iter(|| process_end_to_end(&runtime, fast_reader_rows(workload))));
+    print_peak_memory("end_to_end_fast_reader", || process_end_to_end(&runtime, fast_reader_rows(workload)));
+
+    benchmark_group.finish();
+}
+
+criterion_group!(benches, payments_engine_benchmark);
+criterion_main!(benches);
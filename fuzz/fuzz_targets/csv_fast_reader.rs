@@ -0,0 +1,35 @@
+#![no_main]
+
+use futures::StreamExt;
+use libfuzzer_sys::fuzz_target;
+use simple_payments_engine::OperationRequest;
+use simple_payments_engine::use_case::operation_input::RowRejection;
+use simple_payments_engine::use_case::process_from_csv_use_case::{read_operation_requests_from_csv, read_operation_requests_from_csv_bytes, CsvOperationType};
+
+/*
+  Raw bytes read both by csv_async and by the fast reader: every row, with its line, its request or its rejection message, must be the same.
+  A small buffer gets the fast reader to read lines cut by the end of the buffer too.
+*/
+fn describe_rows(rows: Vec<(u64, Result<OperationRequest, RowRejection>)>) -> Vec<String> {
+    rows.into_iter()
+        .map(|(line, operation_request_result)| match operation_request_result {
+            Ok(OperationRequest::Deposit(deposit)) => format!("{line}: deposit {} {} {} {:?}", deposit.account_id, deposit.transaction_id, deposit.amount, deposit.timestamp),
+            Ok(OperationRequest::Withdrawal(withdrawal)) => format!("{line}: withdrawal {} {} {} {:?}", withdrawal.account_id, withdrawal.transaction_id, withdrawal.amount, withdrawal.timestamp),
+            Ok(operation_request) => format!("{line}: {} {} {:?}", CsvOperationType::of(&operation_request), operation_request.transaction_id(), operation_request.timestamp()),
+            Err(RowRejection::InvalidRequest(operation_type, error_description)) => format!("{line}: {operation_type} {error_description}"),
+            Err(rejection) => format!("{line}: {rejection}")
+        })
+        .collect()
+}
+
+fuzz_target!(|data: &[u8]| {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let (reference_rows, fast_rows) = runtime.block_on(async {
+        let reference_rows = read_operation_requests_from_csv(futures::io::Cursor::new(data.to_vec())).collect().await;
+        let fast_csv_bytes = std::io::BufReader::with_capacity(16, std::io::Cursor::new(data.to_vec()));
+        let fast_rows = read_operation_requests_from_csv_bytes(fast_csv_bytes, futures::io::Cursor::new(data.to_vec())).collect().await;
+        (describe_rows(reference_rows), describe_rows(fast_rows))
+    });
+
+    assert_eq!(fast_rows, reference_rows);
+});
@@ -0,0 +1,23 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use simple_payments_engine::PaymentsEngine;
+use simple_payments_engine::use_case::operation_input::process_operation_requests;
+use simple_payments_engine::use_case::process_from_csv_use_case::read_operation_requests_from_csv_bytes;
+use simple_payments_engine::use_case::processing_session::ProcessingSession;
+
+/*
+  Raw bytes through the whole CSV path, exactly like a file: whatever they are, the engine must not panic
+  and must keep its accounting invariants (checked mode counts the violations in the statistics).
+*/
+fuzz_target!(|data: &[u8]| {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let (_, statistics) = runtime.block_on(async {
+        let mut processing_session = ProcessingSession::new(PaymentsEngine::new().with_invariant_checks());
+        let operation_requests = read_operation_requests_from_csv_bytes(std::io::Cursor::new(data.to_vec()), futures::io::Cursor::new(data.to_vec()));
+        process_operation_requests(operation_requests, "fuzz", &mut processing_session).await;
+        processing_session.finish().unwrap()
+    });
+
+    assert_eq!(statistics.invariant_violations, 0);
+});
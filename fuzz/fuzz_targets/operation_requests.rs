@@ -0,0 +1,89 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use rust_decimal::Decimal;
+use simple_payments_engine::{Amount, BackwardsTimestampsPolicy, ChargebackOperationRequest, DepositOperationRequest, DisputeOperationRequest, EnginePolicy, OperationRequest, PaymentsEngine, ResolveOperationRequest, TimeLimit, Timestamp, WithdrawalOperationRequest};
+
+/*
+  Structured sequences of OperationRequests, skipping the parsing: few clients and transaction ids so that the fuzzer
+  reaches the interesting states quickly, amounts spanning the whole Decimal range and a fuzzed policy.
+*/
+#[derive(Arbitrary, Debug)]
+struct FuzzInput {
+    allow_dispute_with_negative_available_amount: bool,
+    reject_backwards_timestamps: bool,
+    dispute_window_max_operations: Option<u8>,
+    chargeback_deadline_max_operations: Option<u8>,
+    auto_resolve_expired_disputes: bool,
+    operations: Vec<FuzzOperation>
+}
+
+#[derive(Arbitrary, Debug)]
+enum FuzzOperation {
+    Deposit { client: u8, tx: u8, amount: FuzzAmount, timestamp: Option<u16> },
+    Withdrawal { client: u8, tx: u8, amount: FuzzAmount, timestamp: Option<u16> },
+    Dispute { tx: u8, timestamp: Option<u16> },
+    Resolve { tx: u8, timestamp: Option<u16> },
+    Chargeback { tx: u8, timestamp: Option<u16> }
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzAmount {
+    lo: u32,
+    mid: u32,
+    hi: u32,
+    negative: bool,
+    scale: u8
+}
+
+impl FuzzAmount {
+    fn to_amount(&self) -> Amount {
+        Decimal::from_parts(self.lo, self.mid, self.hi, self.negative, u32::from(self.scale % 29))
+    }
+}
+
+fn to_timestamp(maybe_seconds: Option<u16>) -> Option<Timestamp> {
+    maybe_seconds.and_then(|seconds| Timestamp::from_timestamp(i64::from(seconds), 0))
+}
+
+impl FuzzOperation {
+    fn to_operation_request(&self) -> OperationRequest {
+        match *self {
+            FuzzOperation::Deposit { client, tx, ref amount, timestamp } => OperationRequest::Deposit(DepositOperationRequest {
+                account_id: u16::from(client % 8),
+                transaction_id: u32::from(tx % 32),
+                amount: amount.to_amount(),
+                timestamp: to_timestamp(timestamp)
+            }),
+            FuzzOperation::Withdrawal { client, tx, ref amount, timestamp } => OperationRequest::Withdrawal(WithdrawalOperationRequest {
+                account_id: u16::from(client % 8),
+                transaction_id: u32::from(tx % 32),
+                amount: amount.to_amount(),
+                timestamp: to_timestamp(timestamp)
+            }),
+            FuzzOperation::Dispute { tx, timestamp } => OperationRequest::Dispute(DisputeOperationRequest { transaction_id: u32::from(tx % 32), timestamp: to_timestamp(timestamp) }),
+            FuzzOperation::Resolve { tx, timestamp } => OperationRequest::Resolve(ResolveOperationRequest { transaction_id: u32::from(tx % 32), timestamp: to_timestamp(timestamp) }),
+            FuzzOperation::Chargeback { tx, timestamp } => OperationRequest::Chargeback(ChargebackOperationRequest { transaction_id: u32::from(tx % 32), timestamp: to_timestamp(timestamp) })
+        }
+    }
+}
+
+fuzz_target!(|fuzz_input: FuzzInput| {
+    let engine_policy = EnginePolicy {
+        allow_dispute_with_negative_available_amount: fuzz_input.allow_dispute_with_negative_available_amount,
+        backwards_timestamps: if fuzz_input.reject_backwards_timestamps { BackwardsTimestampsPolicy::Reject } else { BackwardsTimestampsPolicy::Warn },
+        dispute_window: TimeLimit { max_seconds: None, max_operations: fuzz_input.dispute_window_max_operations.map(u64::from) },
+        chargeback_deadline: TimeLimit { max_seconds: None, max_operations: fuzz_input.chargeback_deadline_max_operations.map(u64::from) },
+        auto_resolve_expired_disputes: fuzz_input.auto_resolve_expired_disputes,
+        ..EnginePolicy::default()
+    };
+    let mut payments_engine = PaymentsEngine::with_policy(engine_policy).with_invariant_checks();
+
+    for fuzz_operation in &fuzz_input.operations {
+        let _ = payments_engine.process(fuzz_operation.to_operation_request());
+
+        let invariant_violations = payments_engine.take_invariant_violations();
+        assert!(invariant_violations.is_empty(), "{fuzz_operation:?} broke the invariants: {invariant_violations:?}");
+    }
+});
@@ -0,0 +1,11 @@
+#!/bin/sh
+# Seeds the csv_input corpus with the CSV fixtures, so that the fuzzer starts from well-formed inputs covering every case
+set -e
+cd "$(dirname "$0")"
+mkdir -p corpus/csv_input
+for fixture in ../fixtures/*.csv; do
+    cp "$fixture" "corpus/csv_input/$(basename "$fixture")"
+done
+for scenario_input in ../fixtures/*/input.csv; do
+    cp "$scenario_input" "corpus/csv_input/$(basename "$(dirname "$scenario_input")").csv"
+done
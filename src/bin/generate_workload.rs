@@ -0,0 +1,56 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{stdout, BufWriter, Write};
+use clap::Parser;
+use simple_payments_engine::AccountId;
+use simple_payments_engine::use_case::generate_workload_use_case::{write_workload, WorkloadSettings};
+
+/*
+  Generates a realistic CSV of operations to benchmark the engine, e.g.
+  "cargo run --release --bin generate_workload -- --rows 100000000 --output big.csv".
+  The workload is streamed, so any size takes the same memory.
+*/
+#[derive(Parser)]
+#[command(about = "Generates a realistic CSV of operations, to benchmark the payments engine")]
+struct GenerateWorkloadArgs {
+    /// Number of rows to generate
+    #[arg(long, default_value_t = WorkloadSettings::default().rows)]
+    rows: u64,
+    /// Number of distinct clients
+    #[arg(long, default_value_t = WorkloadSettings::default().clients)]
+    clients: AccountId,
+    /// Fraction of the rows that are withdrawals
+    #[arg(long, default_value_t = WorkloadSettings::default().withdrawal_rate)]
+    withdrawal_rate: f64,
+    /// Fraction of the rows that open a dispute
+    #[arg(long, default_value_t = WorkloadSettings::default().dispute_rate)]
+    dispute_rate: f64,
+    /// Fraction of the disputes that end with a chargeback, the other ones are resolved
+    #[arg(long, default_value_t = WorkloadSettings::default().chargeback_rate)]
+    chargeback_rate: f64,
+    /// Seed of the generator: the same seed always generates the same workload
+    #[arg(long, default_value_t = WorkloadSettings::default().seed)]
+    seed: u64,
+    /// Writes the workload to this file instead of stdout
+    #[arg(long)]
+    output: Option<String>
+}
+
+fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let args = GenerateWorkloadArgs::parse();
+    let workload_settings = WorkloadSettings {
+        rows: args.rows,
+        clients: args.clients,
+        withdrawal_rate: args.withdrawal_rate,
+        dispute_rate: args.dispute_rate,
+        chargeback_rate: args.chargeback_rate,
+        seed: args.seed
+    };
+
+    let output: Box<dyn Write> = match &args.output {
+        Some(output_path) => Box::new(File::create(output_path)?),
+        None => Box::new(stdout().lock())
+    };
+    write_workload(&mut BufWriter::new(output), &workload_settings)?;
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use std::str::FromStr;
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{Args, Parser, Subcommand};
+use tracing_subscriber::filter::LevelFilter;
+use simple_payments_engine::{AccountId, Timestamp};
+use simple_payments_engine::output_printer::{AccountSortKey, OutputFormat};
+use simple_payments_engine::use_case::account_statement_use_case::StatementFormat;
+use simple_payments_engine::use_case::operation_input::InputFormat;
+
+/*
+  Running the binary with just a list of inputs is the same as running the "process" subcommand,
+  so that "cargo run -- transactions.csv > accounts.csv" keeps working.
+*/
+#[derive(Parser)]
+#[command(version, about = "A simple payments engine", args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Input files to process, "-" for stdin
+    pub inputs: Vec<String>,
+
+    #[command(flatten)]
+    pub shared_options: SharedOptions
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Processes the inputs in order and prints the resulting accounts
+    Process {
+        /// Input files to process, "-" for stdin
+        #[arg(required = true)]
+        inputs: Vec<String>
+    },
+    /// Dry run: parses and validates the inputs against a scratch engine without producing balances,
+    /// then prints how many rows would be accepted and rejected and why. Exits with 1 if any row is rejected
+    #[command(alias = "dry-run")]
+    Validate {
+        /// Input files to validate, "-" for stdin
+        #[arg(required = true)]
+        inputs: Vec<String>
+    },
+    /// Replays the inputs up to a given row and prints the accounts as they were at that point
+    Replay {
+        /// Number of rows (across all the inputs) to replay
+        #[arg(long)]
+        until_row: u64,
+        /// Input files to replay, "-" for stdin
+        #[arg(required = true)]
+        inputs: Vec<String>
+    },
+    /// Processes the inputs and prints statistics about rows and accounts
+    Stats {
+        /// Input files to process, "-" for stdin
+        #[arg(required = true)]
+        inputs: Vec<String>
+    },
+    /// Processes the inputs and prints the statement of a client account: every applied operation with its effect on the balances
+    Statement {
+        /// Client account to print the statement of
+        #[arg(long)]
+        client: AccountId,
+        /// First operation of the statement (the "operation" column of the account history)
+        #[arg(long)]
+        from_operation: Option<u64>,
+        /// Last operation of the statement
+        #[arg(long)]
+        to_operation: Option<u64>,
+        /// Only the operations from this RFC 3339 timestamp on (operations without a timestamp are always included)
+        #[arg(long, value_parser = parse_timestamp)]
+        from_time: Option<Timestamp>,
+        /// Only the operations up to this RFC 3339 timestamp
+        #[arg(long, value_parser = parse_timestamp)]
+        to_time: Option<Timestamp>,
+        /// Format of the statement
+        #[arg(long, default_value = "text", value_parser = PossibleValuesParser::new(["csv", "text"]).map(|name| StatementFormat::from_name(&name).unwrap()))]
+        statement_format: StatementFormat,
+        /// Input files to process, "-" for stdin
+        #[arg(required = true)]
+        inputs: Vec<String>
+    },
+    /// Processes the inputs and compares the resulting accounts with an expected balances CSV (same shape as the output).
+    /// Prints the missing and extra accounts and the differences per column, and exits with 1 if there is any
+    Reconcile {
+        /// CSV with the expected balances
+        #[arg(long)]
+        expected: String,
+        /// Input files to process, "-" for stdin
+        #[arg(required = true)]
+        inputs: Vec<String>
+    },
+    /// Accepts CSV streams over TCP
+    Serve {
+        address: String
+    },
+    /// Exposes the engine through an HTTP/JSON API
+    ServeHttp {
+        address: String
+    }
+}
+
+#[derive(Args)]
+pub struct SharedOptions {
+    /// Format of the inputs. By default it's guessed from the file extension (.jsonl and .ndjson are JSON Lines, everything else is CSV)
+    #[arg(long, global = true, value_parser = PossibleValuesParser::new(["csv", "jsonl"]).map(|name| InputFormat::from_name(&name).unwrap()))]
+    pub input_format: Option<InputFormat>,
+
+    /// Format of the resulting accounts
+    #[arg(long, global = true, default_value = "csv", value_parser = PossibleValuesParser::new(["csv", "json", "jsonl"]).map(|name| OutputFormat::from_name(&name).unwrap()))]
+    pub output_format: OutputFormat,
+
+    /// Key the resulting accounts are sorted by
+    #[arg(long, global = true, default_value = "client", value_parser = PossibleValuesParser::new(["client", "available", "held", "total"]).map(|name| AccountSortKey::from_name(&name).unwrap()))]
+    pub sort_by: AccountSortKey,
+
+    /// Writes the resulting accounts to this file instead of stdout
+    #[arg(long, global = true)]
+    pub output: Option<String>,
+
+    /// Log level on stderr. By default it's taken from RUST_LOG, falling back to "error"
+    #[arg(long, global = true)]
+    pub log_level: Option<LevelFilter>,
+
+    /// Format of the logs on stderr: "text" for people, "json" (one object per line, with the fields of its spans) for a log platform
+    #[arg(long, global = true, default_value = "text", value_parser = PossibleValuesParser::new(["text", "json"]).map(|name| LogFormat::from_name(&name).unwrap()))]
+    pub log_format: LogFormat,
+
+    /// Writes every rejected row, with the reason why, to this CSV file
+    #[arg(long, global = true)]
+    pub rejections: Option<String>,
+
+    /// Prints the history of this client account (every deposit, withdrawal, dispute, resolve, chargeback and freeze, with the
+    /// balances after it) instead of the resulting accounts
+    #[arg(long, global = true, value_name = "CLIENT")]
+    pub account_history: Option<AccountId>,
+
+    /// Checked mode: verifies the accounting invariants after every operation and logs every violation with its row.
+    /// Slower, meant to catch bugs in the engine
+    #[arg(long, global = true)]
+    pub check_invariants: bool,
+
+    /// Writes the processing metrics (rows per operation type and outcome, validation errors, processing latency)
+    /// to this file in the Prometheus text format at the end of the run
+    #[arg(long, global = true)]
+    pub metrics: Option<String>,
+
+    /// TOML file with the engine policy
+    #[arg(long, global = true)]
+    pub config: Option<String>
+}
+
+#[derive(Clone, Copy)]
+pub enum LogFormat {
+    Text,
+    Json
+}
+
+impl LogFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(LogFormat::Text),
+            "json" => Some(LogFormat::Json),
+            _ => None
+        }
+    }
+}
+
+
+fn parse_timestamp(value: &str) -> Result<Timestamp, String> {
+    Timestamp::from_str(value).map_err(|error| format!("not an RFC 3339 timestamp - {error}"))
+}
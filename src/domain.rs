@@ -1,6 +1,12 @@
-use rust_decimal::Decimal;
+use chrono::{DateTime, Utc};
 
 pub mod account_module;
 pub mod transaction_module;
 pub mod payments_engine;
-pub type Amount = Decimal;
\ No newline at end of file
+pub mod fixed_point_amount;
+
+#[cfg(not(feature = "fixed_point_amount"))]
+pub type Amount = rust_decimal::Decimal;
+#[cfg(feature = "fixed_point_amount")]
+pub type Amount = fixed_point_amount::FixedPointAmount;
+pub type Timestamp = DateTime<Utc>;
\ No newline at end of file
@@ -1,2 +1,4 @@
 pub mod account;
+pub mod account_event;
+pub mod account_history_repository;
 pub mod account_repository;
\ No newline at end of file
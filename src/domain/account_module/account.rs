@@ -1,6 +1,4 @@
-use log::error;
 use crate::domain::Amount;
-use crate::domain::transaction_module::transaction::{DepositTransaction, WithdrawalTransaction};
 
 pub type AccountId = u16;
 
@@ -47,6 +45,12 @@ pub struct ActiveAccount {
     available_amount: Amount,
     held_amount: Amount,
 }
+
+/*
+  Every balance change uses checked arithmetic and returns None when a balance can't be represented exactly anymore:
+  it's up to the validation to turn it into an error, so that a huge amount can't crash the whole batch.
+  An account is only ever built with an exact total, so computing it again can't overflow.
+*/
 impl ActiveAccount {
 
     pub(in crate::domain) fn new(account_id: AccountId) -> Self {
@@ -57,62 +61,71 @@ impl ActiveAccount {
         }
     }
 
+    fn with_balances(&self, maybe_available_amount: Option<Amount>, maybe_held_amount: Option<Amount>) -> Option<ActiveAccount> {
+        let (available_amount, held_amount) = (maybe_available_amount?, maybe_held_amount?);
+        exact_add(available_amount, held_amount)?;
+        Some(ActiveAccount { id: self.id, available_amount, held_amount })
+    }
+
     pub fn id(&self) -> AccountId{
         self.id
     }
 
+    pub fn available_amount(&self) -> Amount {
+        self.available_amount
+    }
+
+    pub fn held_amount(&self) -> Amount {
+        self.held_amount
+    }
+
     pub fn total_amount(&self) -> Amount {
         self.available_amount + self.held_amount
     }
 
-    pub fn deposit(&self, transaction: &DepositTransaction) -> ActiveAccount {
-        ActiveAccount {
-            id: self.id,
-            available_amount: self.available_amount + transaction.amount(),
-            held_amount: self.held_amount
-        }
+    pub fn deposit(&self, amount: Amount) -> Option<ActiveAccount> {
+        self.with_balances(exact_add(self.available_amount, amount), Some(self.held_amount))
     }
 
-    pub fn withdraw(&self, transaction: &WithdrawalTransaction) -> Result<ActiveAccount, ActiveAccount> {
-        let amount_after_withdraw = self.available_amount - transaction.amount();
-
-        if amount_after_withdraw.is_sign_negative() {
-            error!("Impossible to withdraw amount {} from account_module {} - not enough balance: {}", transaction.amount(), self.id, self.available_amount);
-            Err(self.clone())
-        } else {
-            Ok(ActiveAccount {
-                id: self.id,
-                available_amount: amount_after_withdraw,
-                held_amount: self.held_amount
-            })
-        }
+    pub fn withdraw(&self, amount: Amount) -> Option<ActiveAccount> {
+        self.with_balances(exact_sub(self.available_amount, amount), Some(self.held_amount))
     }
 
-    pub fn hold_amount(&self, amount: Amount) -> ActiveAccount {
-        ActiveAccount {
-            id: self.id,
-            available_amount: self.available_amount - amount,
-            held_amount: self.held_amount + amount
-        }
+    pub fn hold_amount(&self, amount: Amount) -> Option<ActiveAccount> {
+        self.with_balances(exact_sub(self.available_amount, amount), exact_add(self.held_amount, amount))
     }
 
-    pub fn release_held_amount(&self, amount: Amount) -> ActiveAccount {
-        ActiveAccount {
-            id: self.id,
-            available_amount: self.available_amount + amount,
-            held_amount: self.held_amount - amount
-        }
+    pub fn release_held_amount(&self, amount: Amount) -> Option<ActiveAccount> {
+        self.with_balances(exact_add(self.available_amount, amount), exact_sub(self.held_amount, amount))
     }
 
-    pub fn charge_back_amount(&self, amount: Amount) -> FrozenAccount {
-        FrozenAccount {
-            id: self.id,
-            available_amount: self.available_amount,
-            held_amount: self.held_amount - amount
-        }
+    pub fn charge_back_amount(&self, amount: Amount) -> Option<FrozenAccount> {
+        self.with_balances(Some(self.available_amount), exact_sub(self.held_amount, amount))
+            .map(|active_account| FrozenAccount {
+                id: active_account.id,
+                available_amount: active_account.available_amount,
+                held_amount: active_account.held_amount
+            })
     }
 }
 
+/*
+  A Decimal has at most 28 significant digits: when the exact result doesn't fit, checked_add rounds it to fewer decimals
+  instead of failing, and the rounding would silently create or destroy money. It's treated as an overflow as well.
+  The result is exact as long as it kept every decimal the operands really have (trailing zeros don't count).
+*/
+pub(crate) fn exact_add(amount: Amount, other_amount: Amount) -> Option<Amount> {
+    amount.checked_add(other_amount).filter(|result| keeps_every_decimal(*result, amount, other_amount))
+}
+
+pub(crate) fn exact_sub(amount: Amount, other_amount: Amount) -> Option<Amount> {
+    amount.checked_sub(other_amount).filter(|result| keeps_every_decimal(*result, amount, other_amount))
+}
+
+fn keeps_every_decimal(result: Amount, amount: Amount, other_amount: Amount) -> bool {
+    result.scale() >= amount.normalize().scale().max(other_amount.normalize().scale())
+}
+
 #[derive(Clone)]
 pub struct FrozenAccount {
     id: AccountId,
@@ -124,4 +137,4 @@ impl FrozenAccount {
     pub fn total_amount(&self) -> Amount {
         self.available_amount + self.held_amount
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,97 @@
+use std::fmt::{Display, Formatter};
+use serde::Serialize;
+use crate::domain::account_module::account::{Account, AccountId};
+use crate::domain::{Amount, Timestamp};
+use crate::domain::payments_engine::operation_request::OperationReference;
+use crate::domain::transaction_module::transaction::TransactionId;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountEventKind {
+    Deposit,
+    Withdrawal,
+    DisputeOpened,
+    DisputeResolved,
+    DisputeExpired,
+    ChargedBack,
+    Frozen
+}
+
+impl Display for AccountEventKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AccountEventKind::Deposit => "deposit",
+            AccountEventKind::Withdrawal => "withdrawal",
+            AccountEventKind::DisputeOpened => "dispute_opened",
+            AccountEventKind::DisputeResolved => "dispute_resolved",
+            AccountEventKind::DisputeExpired => "dispute_expired",
+            AccountEventKind::ChargedBack => "charged_back",
+            AccountEventKind::Frozen => "frozen",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/*
+  Something that happened to an account, together with the balances right after it.
+*/
+#[derive(Clone)]
+pub struct AccountEvent {
+    operation: OperationReference,
+    kind: AccountEventKind,
+    account_id: AccountId,
+    transaction_id: TransactionId,
+    amount: Amount,
+    available_amount: Amount,
+    held_amount: Amount
+}
+
+impl AccountEvent {
+    pub(in crate::domain) fn new(operation: OperationReference, kind: AccountEventKind, transaction_id: TransactionId, amount: Amount, account_after_event: &Account) -> Self {
+        Self {
+            operation,
+            kind,
+            account_id: account_after_event.id(),
+            transaction_id,
+            amount,
+            available_amount: account_after_event.available_amount(),
+            held_amount: account_after_event.held_amount()
+        }
+    }
+
+    pub fn operation_index(&self) -> u64 {
+        self.operation.index
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.operation.timestamp
+    }
+
+    pub fn kind(&self) -> AccountEventKind {
+        self.kind
+    }
+
+    pub fn account_id(&self) -> AccountId {
+        self.account_id
+    }
+
+    pub fn transaction_id(&self) -> TransactionId {
+        self.transaction_id
+    }
+
+    pub fn amount(&self) -> Amount {
+        self.amount
+    }
+
+    pub fn available_amount(&self) -> Amount {
+        self.available_amount
+    }
+
+    pub fn held_amount(&self) -> Amount {
+        self.held_amount
+    }
+
+    pub fn total_amount(&self) -> Amount {
+        self.available_amount + self.held_amount
+    }
+}
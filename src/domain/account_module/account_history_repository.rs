@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+use crate::domain::account_module::account::AccountId;
+use crate::domain::account_module::account_event::AccountEvent;
+
+/*
+  The audit trail of every account, in the order the events happened.
+*/
+pub struct AccountHistoryRepository {
+    events_by_account: HashMap<AccountId, Vec<AccountEvent>>
+}
+
+impl AccountHistoryRepository {
+
+    pub(in crate::domain) fn new() -> Self {
+        Self {
+            events_by_account: HashMap::new()
+        }
+    }
+
+    pub fn find(&self, account_id: AccountId) -> &[AccountEvent] {
+        self.events_by_account
+            .get(&account_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub(in crate::domain) fn store(&mut self, account_event: AccountEvent) {
+        self.events_by_account
+            .entry(account_event.account_id())
+            .or_default()
+            .push(account_event);
+    }
+}
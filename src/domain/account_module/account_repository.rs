@@ -1,15 +1,25 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
 use crate::domain::account_module::account::{Account, AccountId};
 
+/*
+  Accounts are kept ordered by id, so that iterating over them is deterministic across runs.
+*/
 pub struct AccountRepository {
-    accounts: HashMap<AccountId, Account>
+    accounts: BTreeMap<AccountId, Account>
 }
 
+/*
+  The place of an account in the repository, whether it exists yet or not, found with a single lookup:
+  it's read to validate an operation, then the account after the operation is stored in it.
+*/
+pub(in crate::domain) struct AccountSlot<'a>(Entry<'a, AccountId, Account>);
+
 impl AccountRepository {
 
-    pub fn new() -> Self {
+    pub(in crate::domain) fn new() -> Self {
         Self {
-            accounts: HashMap::new()
+            accounts: BTreeMap::new()
         }
     }
 
@@ -21,7 +31,23 @@ impl AccountRepository {
         self.accounts.values()
     }
 
-    pub fn store(&mut self, account: Account) {
-        self.accounts.insert(account.id(), account);
+    pub(in crate::domain) fn slot(&mut self, account_id: AccountId) -> AccountSlot<'_> {
+        AccountSlot(self.accounts.entry(account_id))
+    }
+}
+
+impl AccountSlot<'_> {
+    pub(in crate::domain) fn account(&self) -> Option<&Account> {
+        match &self.0 {
+            Entry::Occupied(occupied_entry) => Some(occupied_entry.get()),
+            Entry::Vacant(_) => None
+        }
+    }
+
+    pub(in crate::domain) fn store(self, account: Account) {
+        match self.0 {
+            Entry::Occupied(mut occupied_entry) => { occupied_entry.insert(account); }
+            Entry::Vacant(vacant_entry) => { vacant_entry.insert(account); }
+        }
     }
 }
\ No newline at end of file
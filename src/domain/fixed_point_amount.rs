@@ -0,0 +1,310 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{Error, MapAccess, Unexpected, Visitor};
+
+/*
+  How many decimals the engine keeps: the CSV reader truncates the amounts to 4 decimals, and the outcome is printed with 4 decimals.
+*/
+pub const FIXED_POINT_SCALE: u32 = 4;
+const MINOR_UNITS: i64 = 10_i64.pow(FIXED_POINT_SCALE);
+
+// The key serde_json uses to hand over the text of a number, when its arbitrary_precision feature is on
+const JSON_NUMBER_KEY: &str = "$serde_json::private::Number";
+
+/*
+  An amount stored as a whole number of minor units (1/10000), so it's plain i64 arithmetic instead of Decimal's 96 bit mantissa and scale.
+  With the fixed_point_amount feature it replaces Decimal behind the Amount alias: it offers the subset of the Decimal API the engine uses,
+  with the same meaning, so that nothing else changes. The range is about ±922 trillion: bigger amounts can't be parsed,
+  and sums that would leave it overflow, exactly like the sums that would leave the range of Decimal.
+  It's always printed with 4 decimals, and there is no negative zero: "-0" is zero.
+*/
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedPointAmount(i64);
+
+#[derive(Debug, PartialEq)]
+pub struct InvalidFixedPointAmount;
+
+impl Display for InvalidFixedPointAmount {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid amount: it must be a number within ±{} with at most {FIXED_POINT_SCALE} meaningful decimals", FixedPointAmount::MAX)
+    }
+}
+
+impl std::error::Error for InvalidFixedPointAmount {}
+
+impl FixedPointAmount {
+    pub const ZERO: FixedPointAmount = FixedPointAmount(0);
+    pub const ONE: FixedPointAmount = FixedPointAmount(MINOR_UNITS);
+    pub const NEGATIVE_ONE: FixedPointAmount = FixedPointAmount(-MINOR_UNITS);
+    pub const MAX: FixedPointAmount = FixedPointAmount(i64::MAX);
+    // Symmetric with MAX, so that every amount can be negated
+    pub const MIN: FixedPointAmount = FixedPointAmount(-i64::MAX);
+
+    pub const fn from_minor_units(minor_units: i64) -> Self {
+        FixedPointAmount(minor_units)
+    }
+
+    pub const fn minor_units(&self) -> i64 {
+        self.0
+    }
+
+    /*
+      Like Decimal::new: num * 10^-scale. Decimals past the 4th are truncated.
+      Instead of panicking out of the range, it saturates at MIN or MAX: try_new reports it, for num and scale that come from an input.
+    */
+    pub fn new(num: i64, scale: u32) -> Self {
+        Self::try_new(num, scale).unwrap_or(if num < 0 { Self::MIN } else { Self::MAX })
+    }
+
+    pub fn try_new(num: i64, scale: u32) -> Result<Self, InvalidFixedPointAmount> {
+        Self::from_scaled(i128::from(num), scale).ok_or(InvalidFixedPointAmount)
+    }
+
+    fn from_scaled(mantissa: i128, scale: u32) -> Option<Self> {
+        let minor_units = if scale >= FIXED_POINT_SCALE {
+            10_i128.checked_pow(scale - FIXED_POINT_SCALE).map_or(0, |divisor| mantissa / divisor)
+        } else {
+            mantissa.checked_mul(10_i128.pow(FIXED_POINT_SCALE - scale))?
+        };
+        i64::try_from(minor_units).ok().map(FixedPointAmount).filter(|amount| *amount >= Self::MIN)
+    }
+
+    pub fn checked_add(self, other: FixedPointAmount) -> Option<FixedPointAmount> {
+        self.0.checked_add(other.0).map(FixedPointAmount).filter(|amount| *amount >= Self::MIN)
+    }
+
+    pub fn checked_sub(self, other: FixedPointAmount) -> Option<FixedPointAmount> {
+        self.0.checked_sub(other.0).map(FixedPointAmount).filter(|amount| *amount >= Self::MIN)
+    }
+
+    pub fn is_sign_negative(&self) -> bool {
+        self.0 < 0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    /*
+      Every amount has exactly 4 decimals: arithmetic never needs to round, so there is nothing to normalize.
+    */
+    pub fn scale(&self) -> u32 {
+        FIXED_POINT_SCALE
+    }
+
+    pub fn normalize(&self) -> FixedPointAmount {
+        *self
+    }
+
+    pub fn trunc_with_scale(&self, scale: u32) -> FixedPointAmount {
+        if scale >= FIXED_POINT_SCALE {
+            *self
+        } else {
+            let unit = 10_i64.pow(FIXED_POINT_SCALE - scale);
+            FixedPointAmount(self.0 / unit * unit)
+        }
+    }
+
+    /*
+      Rounds to the given decimals, midpoint away from zero like Decimal. The amount is still printed with 4 decimals.
+      Near MIN and MAX, where rounding away from zero would leave the range, it's truncated instead.
+    */
+    pub fn rescale(&mut self, scale: u32) {
+        if scale < FIXED_POINT_SCALE {
+            let unit = 10_i64.pow(FIXED_POINT_SCALE - scale);
+            let rounded_units = (i128::from(self.0).abs() + i128::from(unit / 2)) / i128::from(unit) * i128::from(unit);
+            *self = Self::from_scaled(if self.0 < 0 { -rounded_units } else { rounded_units }, FIXED_POINT_SCALE).unwrap_or(self.trunc_with_scale(scale));
+        }
+    }
+
+    /*
+      The text of a number, in any notation Decimal accepts. The plain one is parsed directly,
+      the others (exponents, digit separators) go through Decimal, so that both amounts accept the same input.
+    */
+    fn parse_any_notation(text: &str) -> Result<FixedPointAmount, InvalidFixedPointAmount> {
+        text.parse().or_else(|_| {
+            Decimal::from_str(text)
+                .or_else(|_| Decimal::from_scientific(text))
+                .map_err(|_| InvalidFixedPointAmount)
+                .and_then(FixedPointAmount::try_from)
+        })
+    }
+}
+
+/*
+  An optional sign, digits and an optional decimal point, exactly. Decimals past the 4th are truncated, as long as they're digits.
+*/
+impl FromStr for FixedPointAmount {
+    type Err = InvalidFixedPointAmount;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (negative, unsigned_text) = match text.as_bytes().first() {
+            Some(b'-') => (true, &text[1..]),
+            Some(b'+') => (false, &text[1..]),
+            _ => (false, text)
+        };
+        let (integer_digits, decimal_digits) = unsigned_text.split_once('.').unwrap_or((unsigned_text, ""));
+        if integer_digits.is_empty() && decimal_digits.is_empty() {
+            return Err(InvalidFixedPointAmount);
+        }
+
+        let mut units: i64 = 0;
+        for digit in integer_digits.bytes() {
+            units = units.checked_mul(10).and_then(|units| units.checked_add(digit_value(digit)?)).ok_or(InvalidFixedPointAmount)?;
+        }
+        let mut minor_units = units.checked_mul(MINOR_UNITS).ok_or(InvalidFixedPointAmount)?;
+        let mut decimal_unit = MINOR_UNITS;
+        for digit in decimal_digits.bytes() {
+            let digit_value = digit_value(digit).ok_or(InvalidFixedPointAmount)?;
+            decimal_unit /= 10;
+            minor_units = minor_units.checked_add(digit_value * decimal_unit).ok_or(InvalidFixedPointAmount)?;
+        }
+
+        Ok(FixedPointAmount(if negative { -minor_units } else { minor_units }))
+    }
+}
+
+fn digit_value(digit: u8) -> Option<i64> {
+    digit.is_ascii_digit().then(|| i64::from(digit - b'0'))
+}
+
+/*
+  Truncated to 4 decimals, like the CSV reader does with Decimal amounts.
+*/
+impl TryFrom<Decimal> for FixedPointAmount {
+    type Error = InvalidFixedPointAmount;
+
+    fn try_from(decimal: Decimal) -> Result<Self, Self::Error> {
+        FixedPointAmount::from_scaled(decimal.mantissa(), decimal.scale()).ok_or(InvalidFixedPointAmount)
+    }
+}
+
+impl Display for FixedPointAmount {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let minor_units = self.0.unsigned_abs();
+        write!(f, "{sign}{}.{:04}", minor_units / MINOR_UNITS as u64, minor_units % MINOR_UNITS as u64)
+    }
+}
+
+impl Debug for FixedPointAmount {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+// Panics on overflow, like Decimal: the engine itself only uses the checked operations
+impl Add for FixedPointAmount {
+    type Output = FixedPointAmount;
+
+    fn add(self, other: FixedPointAmount) -> FixedPointAmount {
+        self.checked_add(other).expect("Addition overflowed")
+    }
+}
+
+impl Sub for FixedPointAmount {
+    type Output = FixedPointAmount;
+
+    fn sub(self, other: FixedPointAmount) -> FixedPointAmount {
+        self.checked_sub(other).expect("Subtraction overflowed")
+    }
+}
+
+impl AddAssign for FixedPointAmount {
+    fn add_assign(&mut self, other: FixedPointAmount) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for FixedPointAmount {
+    fn sub_assign(&mut self, other: FixedPointAmount) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for FixedPointAmount {
+    fn sum<I: Iterator<Item = FixedPointAmount>>(amounts: I) -> FixedPointAmount {
+        amounts.fold(FixedPointAmount::ZERO, Add::add)
+    }
+}
+
+/*
+  As a string, like Decimal, so that the outcome is the same.
+*/
+impl Serialize for FixedPointAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/*
+  Asks for the text of the amount, so that the CSV reader hands over the field as it is and it's parsed exactly, without the float
+  the CSV reader infers for Decimal. Formats that know the type of their values (TOML, JSON) can still hand over numbers:
+  integers, floats (through their shortest text) and JSON numbers as text, with serde_json's arbitrary_precision.
+*/
+impl<'de> Deserialize<'de> for FixedPointAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(FixedPointAmountVisitor)
+    }
+}
+
+/*
+  The counterpart of rust_decimal::serde::arbitrary_precision_option, for JSON: an optional amount that can be either a number or a string.
+*/
+pub mod arbitrary_precision_option {
+    use serde::{Deserialize, Deserializer};
+    use super::{FixedPointAmount, FixedPointAmountVisitor};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<FixedPointAmount>, D::Error> {
+        Option::<JsonAmount>::deserialize(deserializer).map(|json_amount| json_amount.map(|json_amount| json_amount.0))
+    }
+
+    struct JsonAmount(FixedPointAmount);
+
+    impl<'de> serde::Deserialize<'de> for JsonAmount {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(FixedPointAmountVisitor).map(JsonAmount)
+        }
+    }
+}
+
+struct FixedPointAmountVisitor;
+
+impl<'de> Visitor<'de> for FixedPointAmountVisitor {
+    type Value = FixedPointAmount;
+
+    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+        write!(formatter, "an amount within ±{}", FixedPointAmount::MAX)
+    }
+
+    fn visit_i64<E: Error>(self, value: i64) -> Result<FixedPointAmount, E> {
+        FixedPointAmount::from_scaled(i128::from(value), 0).ok_or_else(|| E::invalid_value(Unexpected::Signed(value), &self))
+    }
+
+    fn visit_u64<E: Error>(self, value: u64) -> Result<FixedPointAmount, E> {
+        FixedPointAmount::from_scaled(i128::from(value), 0).ok_or_else(|| E::invalid_value(Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_f64<E: Error>(self, value: f64) -> Result<FixedPointAmount, E> {
+        FixedPointAmount::parse_any_notation(&value.to_string()).map_err(|_| E::invalid_value(Unexpected::Float(value), &self))
+    }
+
+    fn visit_str<E: Error>(self, value: &str) -> Result<FixedPointAmount, E> {
+        FixedPointAmount::parse_any_notation(value).map_err(|_| E::invalid_value(Unexpected::Str(value), &self))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<FixedPointAmount, A::Error> {
+        if map.next_key::<String>()?.as_deref() != Some(JSON_NUMBER_KEY) {
+            return Err(A::Error::invalid_type(Unexpected::Map, &self));
+        }
+        let number: String = map.next_value()?;
+        self.visit_str(&number)
+    }
+}
+
+#[cfg(test)]
+mod fixed_point_amount_test;
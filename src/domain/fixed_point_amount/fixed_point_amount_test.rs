@@ -0,0 +1,135 @@
+use std::str::FromStr;
+use rust_decimal::Decimal;
+use crate::domain::fixed_point_amount::{FixedPointAmount, InvalidFixedPointAmount};
+
+#[test]
+fn test_parsing_is_exact_and_truncates_past_four_decimals() {
+    let cases = [
+        ("0", 0),
+        ("1.5", 15_000),
+        ("+1.5", 15_000),
+        ("-1.5", -15_000),
+        (".25", 2_500),
+        ("7.", 70_000),
+        ("0.0001", 1),
+        ("0.00019", 1),
+        ("-0.00019", -1),
+        ("123456789.1234", 1_234_567_891_234),
+        ("922337203685477.5807", i64::MAX),
+        ("-922337203685477.5807", -i64::MAX),
+    ];
+    for (text, minor_units) in cases {
+        assert_eq!(FixedPointAmount::from_str(text), Ok(FixedPointAmount::from_minor_units(minor_units)), "{text}");
+    }
+}
+
+#[test]
+fn test_invalid_or_out_of_range_text_is_rejected() {
+    for text in ["", "-", ".", "1.2.3", "abc", "1,5", " 1", "1e5", "922337203685477.5808", "-922337203685477.5808", "99999999999999999999"] {
+        assert_eq!(FixedPointAmount::from_str(text), Err(InvalidFixedPointAmount), "{text}");
+    }
+}
+
+#[test]
+fn test_formatting_always_has_four_decimals() {
+    assert_eq!(FixedPointAmount::from_minor_units(0).to_string(), "0.0000");
+    assert_eq!(FixedPointAmount::from_minor_units(15_000).to_string(), "1.5000");
+    assert_eq!(FixedPointAmount::from_minor_units(-1).to_string(), "-0.0001");
+    assert_eq!(FixedPointAmount::MAX.to_string(), "922337203685477.5807");
+    assert_eq!(FixedPointAmount::MIN.to_string(), "-922337203685477.5807");
+}
+
+#[test]
+fn test_arithmetic_overflow_is_reported() {
+    assert_eq!(FixedPointAmount::MAX.checked_add(FixedPointAmount::from_minor_units(1)), None);
+    assert_eq!(FixedPointAmount::MIN.checked_sub(FixedPointAmount::from_minor_units(1)), None);
+    assert_eq!(FixedPointAmount::MAX.checked_sub(FixedPointAmount::ONE), Some(FixedPointAmount::from_minor_units(i64::MAX - 10_000)));
+    assert_eq!(FixedPointAmount::ONE.checked_add(FixedPointAmount::NEGATIVE_ONE), Some(FixedPointAmount::ZERO));
+}
+
+#[test]
+fn test_scaling_matches_decimal() {
+    assert_eq!(FixedPointAmount::new(123_456, 5), FixedPointAmount::from_minor_units(12_345));
+    assert_eq!(FixedPointAmount::new(-15, 1), FixedPointAmount::from_minor_units(-15_000));
+    assert_eq!(FixedPointAmount::new(12_345, 4).trunc_with_scale(2), FixedPointAmount::new(123, 2));
+    assert_eq!(FixedPointAmount::new(-12_345, 4).trunc_with_scale(0), FixedPointAmount::new(-1, 0));
+
+    let mut rounded_up = FixedPointAmount::new(125, 3);
+    rounded_up.rescale(2);
+    assert_eq!(rounded_up, FixedPointAmount::new(13, 2));
+    let mut rounded_away_from_zero = FixedPointAmount::new(-125, 3);
+    rounded_away_from_zero.rescale(2);
+    assert_eq!(rounded_away_from_zero, FixedPointAmount::new(-13, 2));
+
+    let mut truncated_near_max = FixedPointAmount::MAX;
+    truncated_near_max.rescale(0);
+    assert_eq!(truncated_near_max, FixedPointAmount::MAX.trunc_with_scale(0));
+    let mut truncated_near_min = FixedPointAmount::MIN;
+    truncated_near_min.rescale(2);
+    assert_eq!(truncated_near_min, FixedPointAmount::MIN.trunc_with_scale(2));
+
+    assert_eq!(FixedPointAmount::try_new(i64::MAX, 0), Err(InvalidFixedPointAmount));
+    assert_eq!(FixedPointAmount::try_new(15, 40), Ok(FixedPointAmount::ZERO));
+    assert_eq!(FixedPointAmount::new(i64::MAX, 2), FixedPointAmount::MAX);
+    assert_eq!(FixedPointAmount::new(i64::MIN, 0), FixedPointAmount::MIN);
+
+    assert_eq!(FixedPointAmount::try_from(Decimal::new(-123_456_789, 8)), Ok(FixedPointAmount::from_minor_units(-12_345)));
+    assert_eq!(FixedPointAmount::try_from(Decimal::MAX), Err(InvalidFixedPointAmount));
+}
+
+#[test]
+fn test_deserialization_accepts_what_decimal_accepts() {
+    #[derive(serde::Deserialize)]
+    struct AmountRow {
+        #[allow(dead_code)]
+        client: u16,
+        amount: Option<FixedPointAmount>
+    }
+
+    let csv = "client,amount\n1,1.5\n1,2\n1,-3.25\n1,1_000.5\n1,0.123456\n1,0.99999999999999999999\n1,\n";
+    let amounts: Vec<Option<FixedPointAmount>> = csv::Reader::from_reader(csv.as_bytes())
+        .deserialize::<AmountRow>()
+        .map(|row| row.unwrap().amount)
+        .collect();
+    assert_eq!(amounts, vec![
+        Some(FixedPointAmount::new(15, 1)),
+        Some(FixedPointAmount::new(2, 0)),
+        Some(FixedPointAmount::new(-325, 2)),
+        Some(FixedPointAmount::new(10_005, 1)),
+        Some(FixedPointAmount::new(1_234, 4)),
+        Some(FixedPointAmount::new(9_999, 4)),
+        None
+    ]);
+
+    #[derive(serde::Deserialize)]
+    struct JsonAmountRow {
+        #[serde(default, with = "crate::domain::fixed_point_amount::arbitrary_precision_option")]
+        amount: Option<FixedPointAmount>
+    }
+
+    let json_amounts: Vec<Option<FixedPointAmount>> = [r#"{"amount": 1.5}"#, r#"{"amount": "2.25"}"#, r#"{"amount": 3}"#, r#"{"amount": 1e2}"#, r#"{"amount": 0.12345678901234567890123}"#, "{}"]
+        .into_iter()
+        .map(|json_row| serde_json::from_str::<JsonAmountRow>(json_row).unwrap().amount)
+        .collect();
+    assert_eq!(json_amounts, vec![
+        Some(FixedPointAmount::new(15, 1)),
+        Some(FixedPointAmount::new(225, 2)),
+        Some(FixedPointAmount::new(3, 0)),
+        Some(FixedPointAmount::new(100, 0)),
+        Some(FixedPointAmount::new(1_234, 4)),
+        None
+    ]);
+    assert!(serde_json::from_str::<JsonAmountRow>(r#"{"amount": "abc"}"#).is_err());
+    assert!(serde_json::from_str::<JsonAmountRow>(r#"{"amount": 1e30}"#).is_err());
+
+    #[derive(serde::Deserialize)]
+    struct TomlAmount {
+        amount: FixedPointAmount
+    }
+
+    for (toml_amount, expected_amount) in [("1000", FixedPointAmount::new(1000, 0)), ("1000.5", FixedPointAmount::new(10_005, 1)), (r#""1000.25""#, FixedPointAmount::new(100_025, 2))] {
+        assert_eq!(toml::from_str::<TomlAmount>(&format!("amount = {toml_amount}")).unwrap().amount, expected_amount);
+    }
+
+    assert_eq!(serde_json::to_string(&FixedPointAmount::new(15, 1)).unwrap(), r#""1.5000""#);
+}
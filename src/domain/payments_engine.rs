@@ -1,26 +1,92 @@
 mod valid_operation_request;
 pub mod operation_request;
+pub mod engine_policy;
+pub mod invariant_check;
+pub mod engine_metrics;
+mod open_disputes;
 
-use log::error;
+use std::collections::HashMap;
+use std::time::Instant;
+use tracing::field::Empty;
 use valid_operation_request::ValidOperationRequest;
-use crate::domain::account_module::account::{Account, ActiveAccount};
-use crate::domain::account_module::account_repository::AccountRepository;
-use crate::domain::payments_engine::operation_request::OperationRequest;
-use crate::domain::transaction_module::transaction::{DepositTransaction, DisputedDepositTransaction, Transaction, WithdrawalTransaction};
-use crate::domain::transaction_module::transaction_repository::TransactionRepository;
+use crate::domain::{Amount, Timestamp};
+use crate::domain::account_module::account::{Account, AccountId, ActiveAccount, FrozenAccount};
+use crate::domain::account_module::account_event::{AccountEvent, AccountEventKind};
+use crate::domain::account_module::account_history_repository::AccountHistoryRepository;
+use crate::domain::account_module::account_repository::{AccountRepository, AccountSlot};
+use crate::domain::payments_engine::engine_metrics::EngineMetrics;
+use crate::domain::payments_engine::engine_policy::EnginePolicy;
+use crate::domain::payments_engine::invariant_check::{check_account_invariants, HistoryBalances, InvariantViolation};
+use crate::domain::payments_engine::open_disputes::OpenDisputes;
+use crate::domain::payments_engine::operation_request::{OperationReference, OperationRequest};
+use crate::domain::transaction_module::transaction::{ChargedBackDepositTransaction, DepositTransaction, DisputedDepositTransaction, Transaction, TransactionId, WithdrawalTransaction};
+use crate::domain::transaction_module::transaction_repository::{TransactionRepository, TransactionSlot};
+
+pub use valid_operation_request::OperationValidationError;
+
 pub struct PaymentsEngine {
     account_repository: AccountRepository,
-    transaction_repository: TransactionRepository
+    transaction_repository: TransactionRepository,
+    account_history_repository: AccountHistoryRepository,
+    engine_policy: EnginePolicy,
+    operations_submitted: u64,
+    latest_timestamp: Option<Timestamp>,
+    // To auto-resolve the expired disputes
+    open_disputes: OpenDisputes,
+    check_invariants: bool,
+    history_balances: HashMap<AccountId, HistoryBalances>,
+    invariant_violations: Vec<InvariantViolation>,
+    metrics: Option<EngineMetrics>
+}
+
+impl Default for PaymentsEngine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PaymentsEngine {
     pub fn new() -> Self {
+        Self::with_policy(EnginePolicy::default())
+    }
+
+    pub fn with_policy(engine_policy: EnginePolicy) -> Self {
         Self {
             account_repository: AccountRepository::new(),
-            transaction_repository: TransactionRepository::new()
+            transaction_repository: TransactionRepository::new(),
+            account_history_repository: AccountHistoryRepository::new(),
+            engine_policy,
+            operations_submitted: 0,
+            latest_timestamp: None,
+            open_disputes: OpenDisputes::default(),
+            check_invariants: false,
+            history_balances: HashMap::new(),
+            invariant_violations: vec![],
+            metrics: None
         }
     }
 
+    /*
+        Checked mode: after every operation, the accounts it touched are checked against the accounting invariants.
+        It's meant to catch bugs in the engine, so it's off by default: it copies the account before every operation and keeps
+        a second set of balances for every account, added up from its history.
+    */
+    pub fn with_invariant_checks(self) -> Self {
+        Self { check_invariants: true, ..self }
+    }
+
+    /*
+        Metrics are off by default too, so that processing doesn't pay for reading the clock twice per operation when nobody looks at them.
+        The time spent checking the invariants isn't part of the processing latency.
+    */
+    pub fn with_metrics(self) -> Self {
+        Self { metrics: Some(EngineMetrics::default()), ..self }
+    }
+
+    pub fn metrics(&self) -> Option<&EngineMetrics> {
+        self.metrics.as_ref()
+    }
+
 
     /*
         This is the only entry point for the PaymentsEngine. It expects an OperationRequest and tries
@@ -28,82 +94,235 @@ impl PaymentsEngine {
         ValidOperationRequest represents a validated operation, which will be processed by the engine.
         I'm following the "parse, don't validate" approach, so whenever we have a ValidOperationRequest,
         logic will run flawlessly and will not generate any Runtime issue. The processing logic is "pure".
+        The validation errors are returned to the caller, which knows where the request comes from and how to report them.
     */
-    pub fn process(&mut self, operation_request: OperationRequest, index: usize) {
-        let valid_operation_request = ValidOperationRequest::new(&operation_request, &self.account_repository, &self.transaction_repository);
-        match valid_operation_request {
+    pub fn process(&mut self, operation_request: OperationRequest) -> Result<(), Vec<OperationValidationError>> {
+        let started_at = self.metrics.is_some().then(Instant::now);
+        // Every submitted request, valid or not, gets the next operation index, which is what account events refer to
+        self.operations_submitted += 1;
+        let operation = OperationReference { index: self.operations_submitted, timestamp: operation_request.timestamp() };
+        // A debug span costs next to nothing with the default log level; the client is known only once the referenced transaction is found
+        let operation_span = tracing::debug_span!("process_operation", operation = operation.index, operation_type = operation_request.type_name(), client = Empty, tx = operation_request.transaction_id(), outcome = Empty, errors = Empty);
+        let _operation_span_guard = operation_span.enter();
+        let account_before = if self.check_invariants { self.account_of(&operation_request).cloned() } else { None };
+
+        // Looked up once: the validation reads the transaction and the account from their slots, the processing stores the updated ones in the same slots
+        let transaction_slot = self.transaction_repository.slot(operation_request.transaction_id());
+        let maybe_account_id = account_id_of(&operation_request, transaction_slot.transaction());
+        operation_span.record("client", maybe_account_id);
+        let maybe_account_slot = maybe_account_id.map(|account_id| self.account_repository.slot(account_id));
+        let maybe_account = maybe_account_slot.as_ref().and_then(AccountSlot::account);
+
+        let process_result = match (ValidOperationRequest::new(&operation_request, operation, self.latest_timestamp, maybe_account, transaction_slot.transaction(), &self.engine_policy), maybe_account_slot) {
+            (Ok(valid_operation_request), Some(account_slot)) => {
+                if let ValidOperationRequest::OpenDispute { disputed_transaction, .. } = &valid_operation_request
+                    && self.engine_policy.auto_resolve_expired_disputes {
+                    self.open_disputes.push(disputed_transaction.id(), operation, &self.engine_policy.chargeback_deadline);
+                }
+                process_valid_operation_request(valid_operation_request, operation, account_slot, transaction_slot, &mut self.account_history_repository);
+                // Only processed operations move it forward, so a rejected one can't push the others out of order
+                self.latest_timestamp = self.latest_timestamp.max(operation.timestamp);
+                Ok(())
+            }
+            // Only a request on an unknown transaction has no account to look up, and it can't be valid
+            (Ok(_), None) => unreachable!("A valid operation request is always on an account"),
+            (Err(errors), _) => Err(errors)
+        };
+        match &process_result {
+            Ok(()) => operation_span.record("outcome", "accepted"),
+            Err(errors) => operation_span.record("outcome", "rejected").record("errors", tracing::field::debug(errors))
+        };
+
+        let auto_resolved_account_ids = if self.engine_policy.auto_resolve_expired_disputes {
+            // A backwards timestamp doesn't move the time back: disputes already expired by a later operation stay expired
+            self.resolve_expired_disputes(OperationReference { timestamp: operation.timestamp.max(self.latest_timestamp), ..operation })
+        } else {
+            vec![]
+        };
+
+        if let (Some(metrics), Some(started_at)) = (self.metrics.as_mut(), started_at) {
+            metrics.record(started_at.elapsed());
+        }
+
+        if self.check_invariants {
+            if process_result.is_ok() {
+                self.check_account_invariants(operation.index, account_before.as_ref(), self.account_of(&operation_request).map(Account::id));
+            }
+            for account_id in auto_resolved_account_ids {
+                self.check_account_invariants(operation.index, None, Some(account_id));
+            }
+        }
 
-            Ok(valid_operation_request) => process_valid_operation_request(valid_operation_request, &mut self.account_repository, &mut self.transaction_repository),
+        process_result
+    }
+
+    /*
+        Validates an operation request against the current state, as if it were the next one, without applying it.
+    */
+    pub fn validate(&self, operation_request: &OperationRequest) -> Result<(), Vec<OperationValidationError>> {
+        let operation = OperationReference { index: self.operations_submitted + 1, timestamp: operation_request.timestamp() };
+        let maybe_transaction = self.transaction_repository.find(operation_request.transaction_id());
+        let maybe_account = account_id_of(operation_request, maybe_transaction).and_then(|account_id| self.account_repository.find(account_id));
+        ValidOperationRequest::new(operation_request, operation, self.latest_timestamp, maybe_account, maybe_transaction, &self.engine_policy)
+            .map(|_| ())
+    }
 
-            Err(errors) => {
-                let errors_as_string  = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>().join(", ");
-                error!("Impossible to process operation request number {index} - Errors: {errors_as_string}");
-            },
+    /*
+        The violations found in checked mode since the last call. The caller knows where the operations come from, so it's the one reporting them:
+        it has to take them after every operation, like the processing session does.
+    */
+    pub fn take_invariant_violations(&mut self) -> Vec<InvariantViolation> {
+        std::mem::take(&mut self.invariant_violations)
+    }
 
+    fn check_account_invariants(&mut self, operation_index: u64, account_before: Option<&Account>, maybe_account_id: Option<AccountId>) {
+        if let Some(account_after) = maybe_account_id.and_then(|account_id| self.account_repository.find(account_id)) {
+            let history_balances = self.history_balances.entry(account_after.id()).or_default();
+            history_balances.add_up_new_events(self.account_history_repository.find(account_after.id()));
+            let invariant_violations = check_account_invariants(operation_index, account_before, account_after, history_balances);
+            self.invariant_violations.extend(invariant_violations);
         }
     }
 
+    fn account_of(&self, operation_request: &OperationRequest) -> Option<&Account> {
+        account_id_of(operation_request, self.transaction(operation_request.transaction_id()))
+            .and_then(|account_id| self.account_repository.find(account_id))
+    }
+
+    /*
+        Every submitted operation moves the clock forward, so after each one the open disputes are checked against the
+        chargeback deadline, in the order they expire, stopping at the first ones that are not expired yet.
+        Disputes already resolved or charged back (or opened again later) are just dropped,
+        while the ones on a frozen account stay held: nothing can change a frozen account (same for a release that would overflow).
+    */
+    fn resolve_expired_disputes(&mut self, now: OperationReference) -> Vec<AccountId> {
+        let mut resolved_account_ids = vec![];
+        let chargeback_deadline = self.engine_policy.chargeback_deadline;
+        while let Some((transaction_id, dispute_operation)) = self.open_disputes.pop_first_done(|transaction_id, dispute_operation| {
+            still_open_dispute(self.transaction_repository.find(transaction_id), dispute_operation).is_none() || chargeback_deadline.is_expired(dispute_operation, now)
+        }) {
+            let transaction_slot = self.transaction_repository.slot(transaction_id);
+            let Some(disputed_deposit_transaction) = still_open_dispute(transaction_slot.transaction(), dispute_operation) else {
+                continue;
+            };
+
+            let account_slot = self.account_repository.slot(disputed_deposit_transaction.to_account_id());
+            if let Some(Account::Active(active_account)) = account_slot.account()
+                && let Some(updated_account) = active_account.release_held_amount(disputed_deposit_transaction.amount()) {
+                tracing::info!(client = updated_account.id(), tx = transaction_id, "Dispute expired, resolving it");
+                let resolved_transaction = disputed_deposit_transaction.resolve_dispute();
+                resolved_account_ids.push(updated_account.id());
+                let (updated_account, updated_transaction, account_events) = process_resolve_dispute(resolved_transaction, updated_account, now, AccountEventKind::DisputeExpired);
+                account_slot.store(updated_account);
+                transaction_slot.store(updated_transaction);
+                account_events.into_iter().for_each(|account_event| self.account_history_repository.store(account_event));
+            }
+        }
+        resolved_account_ids
+    }
+
+    pub fn policy(&self) -> &EnginePolicy {
+        &self.engine_policy
+    }
+
     pub fn accounts(&self) -> impl Iterator<Item = &Account> {
         self.account_repository.all()
     }
 
-}
-
+    pub fn account(&self, account_id: AccountId) -> Option<&Account> {
+        self.account_repository.find(account_id)
+    }
 
+    pub fn transaction(&self, transaction_id: TransactionId) -> Option<&Transaction> {
+        self.transaction_repository.find(transaction_id)
+    }
 
-fn process_valid_operation_request(valid_operation_request: ValidOperationRequest, account_repository: &mut AccountRepository, transaction_repository: &mut TransactionRepository) {
+    pub fn account_history(&self, account_id: AccountId) -> &[AccountEvent] {
+        self.account_history_repository.find(account_id)
+    }
 
-    let updated_account = match valid_operation_request {
-        ValidOperationRequest::Deposit { new_transaction, to_account } => process_deposit(new_transaction, to_account, transaction_repository),
-        ValidOperationRequest::Withdrawal { new_transaction, from_account } => process_withdrawal(new_transaction, from_account, transaction_repository),
-        ValidOperationRequest::OpenDispute { on_transaction, account } => process_open_dispute(on_transaction, account, transaction_repository),
-        ValidOperationRequest::ResolveDispute { on_transaction, account } => process_resolve_dispute(on_transaction, account, transaction_repository),
-        ValidOperationRequest::ChargeBack { on_transaction, account } => process_chargeback(on_transaction, account, transaction_repository),
-    };
+    /*
+        The transactions that touched the account, in the order they first did, with their current state.
+    */
+    pub fn account_transactions(&self, account_id: AccountId) -> impl Iterator<Item = &Transaction> {
+        let mut seen_transaction_ids = std::collections::HashSet::new();
+        self.account_history(account_id)
+            .iter()
+            .map(AccountEvent::transaction_id)
+            .filter(move |transaction_id| seen_transaction_ids.insert(*transaction_id))
+            .filter_map(|transaction_id| self.transaction_repository.find(transaction_id))
+    }
 
-    account_repository.store(updated_account);
 }
 
-fn process_deposit(deposit_transaction: DepositTransaction, to_account: ActiveAccount, transaction_repository: &mut TransactionRepository) -> Account {
-    let updated_account = to_account.deposit(&deposit_transaction);
-    transaction_repository.store(Transaction::Deposit(deposit_transaction));
-    Account::Active(updated_account)
+
+
+fn still_open_dispute(maybe_transaction: Option<&Transaction>, dispute_operation: OperationReference) -> Option<&DisputedDepositTransaction> {
+    match maybe_transaction {
+        Some(Transaction::DisputedDeposit(disputed_deposit_transaction)) if disputed_deposit_transaction.dispute_operation() == dispute_operation => Some(disputed_deposit_transaction),
+        _ => None
+    }
 }
 
-fn process_withdrawal(withdrawal_transaction: WithdrawalTransaction, from_account: ActiveAccount, transaction_repository: &mut TransactionRepository) -> Account {
-    let updated_account_result = from_account.withdraw(&withdrawal_transaction);
-    match updated_account_result {
-        Ok(updated_account) => {
-            transaction_repository.store(Transaction::Withdrawal(withdrawal_transaction));
-            Account::Active(updated_account)
-        }
-        Err(active_account) => {
-            Account::Active(active_account)
-        }
+/*
+  Every operation is on the account of its request, or on the one of the transaction it refers to.
+*/
+fn account_id_of(operation_request: &OperationRequest, maybe_transaction: Option<&Transaction>) -> Option<AccountId> {
+    match operation_request {
+        OperationRequest::Deposit(deposit_operation_request) => Some(deposit_operation_request.account_id),
+        OperationRequest::Withdrawal(withdrawal_operation_request) => Some(withdrawal_operation_request.account_id),
+        OperationRequest::Dispute(_) | OperationRequest::Resolve(_) | OperationRequest::Chargeback(_) => maybe_transaction.map(Transaction::account_id)
     }
+}
+
+fn process_valid_operation_request(valid_operation_request: ValidOperationRequest, operation: OperationReference, account_slot: AccountSlot, transaction_slot: TransactionSlot, account_history_repository: &mut AccountHistoryRepository) {
+
+    let (updated_account, updated_transaction, account_events) = match valid_operation_request {
+        ValidOperationRequest::Deposit { new_transaction, updated_account } => process_deposit(new_transaction, updated_account, operation),
+        ValidOperationRequest::Withdrawal { new_transaction, updated_account } => process_withdrawal(new_transaction, updated_account, operation),
+        ValidOperationRequest::OpenDispute { disputed_transaction, updated_account } => process_open_dispute(disputed_transaction, updated_account, operation),
+        ValidOperationRequest::ResolveDispute { resolved_transaction, updated_account } => process_resolve_dispute(resolved_transaction, updated_account, operation, AccountEventKind::DisputeResolved),
+        ValidOperationRequest::ChargeBack { charged_back_transaction, updated_account } => process_chargeback(charged_back_transaction, updated_account, operation),
+    };
 
+    account_slot.store(updated_account);
+    transaction_slot.store(updated_transaction);
+    account_events.into_iter().for_each(|account_event| account_history_repository.store(account_event));
 }
 
-fn process_open_dispute(deposit_transaction: DepositTransaction, account: ActiveAccount, transaction_repository: &mut TransactionRepository) -> Account {
-    let updated_account = account.hold_amount(deposit_transaction.amount());
-    let updated_transaction = deposit_transaction.open_dispute();
-    transaction_repository.store(Transaction::DisputedDeposit(updated_transaction));
+fn process_deposit(deposit_transaction: DepositTransaction, updated_account: ActiveAccount, operation: OperationReference) -> (Account, Transaction, Vec<AccountEvent>) {
+    let updated_account = Account::Active(updated_account);
+    let account_event = AccountEvent::new(operation, AccountEventKind::Deposit, deposit_transaction.id(), deposit_transaction.amount(), &updated_account);
+    (updated_account, Transaction::Deposit(deposit_transaction), vec![account_event])
+}
 
-    Account::Active(updated_account)
+fn process_withdrawal(withdrawal_transaction: WithdrawalTransaction, updated_account: ActiveAccount, operation: OperationReference) -> (Account, Transaction, Vec<AccountEvent>) {
+    let updated_account = Account::Active(updated_account);
+    let account_event = AccountEvent::new(operation, AccountEventKind::Withdrawal, withdrawal_transaction.id(), withdrawal_transaction.amount(), &updated_account);
+    (updated_account, Transaction::Withdrawal(withdrawal_transaction), vec![account_event])
 }
 
-fn process_resolve_dispute(disputed_deposit_transaction: DisputedDepositTransaction, account: ActiveAccount, transaction_repository: &mut TransactionRepository) -> Account {
-    let updated_account = account.release_held_amount(disputed_deposit_transaction.amount());
-    let updated_transaction = disputed_deposit_transaction.resolve_dispute();
-    transaction_repository.store(Transaction::Deposit(updated_transaction));
+fn process_open_dispute(disputed_deposit_transaction: DisputedDepositTransaction, updated_account: ActiveAccount, operation: OperationReference) -> (Account, Transaction, Vec<AccountEvent>) {
+    let updated_account = Account::Active(updated_account);
+    let account_event = AccountEvent::new(operation, AccountEventKind::DisputeOpened, disputed_deposit_transaction.id(), disputed_deposit_transaction.amount(), &updated_account);
+    (updated_account, Transaction::DisputedDeposit(disputed_deposit_transaction), vec![account_event])
+}
 
-    Account::Active(updated_account)
+fn process_resolve_dispute(resolved_deposit_transaction: DepositTransaction, updated_account: ActiveAccount, operation: OperationReference, account_event_kind: AccountEventKind) -> (Account, Transaction, Vec<AccountEvent>) {
+    let updated_account = Account::Active(updated_account);
+    let account_event = AccountEvent::new(operation, account_event_kind, resolved_deposit_transaction.id(), resolved_deposit_transaction.amount(), &updated_account);
+    (updated_account, Transaction::Deposit(resolved_deposit_transaction), vec![account_event])
 }
 
-fn process_chargeback(disputed_deposit_transaction: DisputedDepositTransaction, account: ActiveAccount, transaction_repository: &mut TransactionRepository) -> Account {
-    let updated_account = account.charge_back_amount(disputed_deposit_transaction.amount());
-    let updated_transaction = disputed_deposit_transaction.charge_back();
-    transaction_repository.store(Transaction::ChargedBackDeposit(updated_transaction));
+fn process_chargeback(charged_back_transaction: ChargedBackDepositTransaction, updated_account: FrozenAccount, operation: OperationReference) -> (Account, Transaction, Vec<AccountEvent>) {
+    let updated_account = Account::Frozen(updated_account);
+    let charged_back_transaction = Transaction::ChargedBackDeposit(charged_back_transaction);
+    let account_events = vec![
+        AccountEvent::new(operation, AccountEventKind::ChargedBack, charged_back_transaction.id(), charged_back_transaction.amount(), &updated_account),
+        AccountEvent::new(operation, AccountEventKind::Frozen, charged_back_transaction.id(), Amount::ZERO, &updated_account),
+    ];
+    (updated_account, charged_back_transaction, account_events)
+}
 
-    Account::Frozen(updated_account)
-}
\ No newline at end of file
+#[cfg(test)]
+mod payments_engine_test;
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+/*
+  Upper bounds of the processing latency buckets: an operation usually takes around a microsecond,
+  the slow ones are the ones resolving many expired disputes at once or growing the repositories.
+*/
+pub const LATENCY_BUCKET_BOUNDS: [Duration; 10] = [
+    Duration::from_nanos(250),
+    Duration::from_nanos(500),
+    Duration::from_micros(1),
+    Duration::from_nanos(2_500),
+    Duration::from_micros(5),
+    Duration::from_micros(10),
+    Duration::from_micros(25),
+    Duration::from_micros(100),
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+];
+
+/*
+  How many observations fell in each bucket (the last one is past every bound), their count and their sum.
+*/
+#[derive(Clone, Default)]
+pub struct LatencyHistogram {
+    bucket_counts: [u64; LATENCY_BUCKET_BOUNDS.len() + 1],
+    count: u64,
+    sum: Duration
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency: Duration) {
+        let bucket_index = LATENCY_BUCKET_BOUNDS.iter().position(|bucket_bound| latency <= *bucket_bound).unwrap_or(LATENCY_BUCKET_BOUNDS.len());
+        self.bucket_counts[bucket_index] += 1;
+        self.count += 1;
+        self.sum += latency;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    /*
+      Every bound with the number of observations up to it, like Prometheus wants them: None is the bucket without a bound (+Inf).
+    */
+    pub fn cumulative_buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        LATENCY_BUCKET_BOUNDS.iter().copied().map(Some).chain([None])
+            .zip(self.bucket_counts.iter().scan(0, |cumulative_count, bucket_count| {
+                *cumulative_count += bucket_count;
+                Some(*cumulative_count)
+            }))
+    }
+}
+
+/*
+  What the engine measures about itself when metrics are on: how long processing each operation took, whatever input they come from.
+  Why operations were rejected is already in the errors returned by process, so it's counted once by the caller (the processing session).
+*/
+#[derive(Clone, Default)]
+pub struct EngineMetrics {
+    pub processing_latency: LatencyHistogram
+}
+
+impl EngineMetrics {
+    pub(in crate::domain::payments_engine) fn record(&mut self, latency: Duration) {
+        self.processing_latency.record(latency);
+    }
+}
@@ -0,0 +1,91 @@
+use serde::Deserialize;
+use crate::domain::Amount;
+use crate::domain::payments_engine::operation_request::OperationReference;
+
+/*
+  The business rules that can be tuned without touching the code, e.g. through a configuration file.
+  The default policy is the behavior described in the README assumptions.
+*/
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnginePolicy {
+    /*
+      A dispute can't be ignored, so by default it's accepted even if it makes the available amount negative.
+      When disabled, such disputes are rejected with NotEnoughAvailableAmount.
+    */
+    pub allow_dispute_with_negative_available_amount: bool,
+    /*
+      What to do with an operation whose timestamp is before the one of an already processed operation.
+    */
+    pub backwards_timestamps: BackwardsTimestampsPolicy,
+    /*
+      How many operations are held back and sorted by timestamp before being processed, with the "reorder" policy.
+    */
+    pub reorder_window: usize,
+    /*
+      How long after a deposit it can still be disputed. Disputes past it are rejected with DisputeWindowExpired.
+    */
+    pub dispute_window: TimeLimit,
+    /*
+      How long after a dispute is opened it can still be charged back. Chargebacks past it are rejected with ChargebackDeadlineExpired.
+    */
+    pub chargeback_deadline: TimeLimit,
+    /*
+      When enabled, disputes still open past the chargeback deadline are resolved automatically, releasing the held amount.
+    */
+    pub auto_resolve_expired_disputes: bool,
+    /*
+      The maximum available, held and total amount of an account. Operations going past it, or past what an amount can represent,
+      are rejected with BalanceOverflow. Without it, only the representable amount is a limit.
+    */
+    pub max_balance: Option<Amount>
+}
+
+/*
+  A limit on the distance between two operations. The time limit applies when both operations have a timestamp,
+  the operations limit (how many operations have been submitted to the engine in between) otherwise.
+  Without limits, nothing ever expires.
+*/
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeLimit {
+    pub max_seconds: Option<u64>,
+    pub max_operations: Option<u64>
+}
+
+impl TimeLimit {
+    pub fn is_expired(&self, since: OperationReference, now: OperationReference) -> bool {
+        match (self.max_seconds, since.timestamp, now.timestamp) {
+            (Some(max_seconds), Some(since_timestamp), Some(now_timestamp)) => (now_timestamp - since_timestamp).num_seconds() > max_seconds as i64,
+            _ => self.max_operations.is_some_and(|max_operations| now.index.saturating_sub(since.index) > max_operations)
+        }
+    }
+}
+
+/*
+  Operations without a timestamp are never affected.
+*/
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum BackwardsTimestampsPolicy {
+    // The operation is rejected with TimestampOutOfOrder
+    Reject,
+    // The operation is processed anyway, logging a warning
+    Warn,
+    // Operations are sorted by timestamp within the reorder window; the ones arriving later than that are rejected
+    Reorder
+}
+
+impl Default for EnginePolicy {
+    fn default() -> Self {
+        EnginePolicy {
+            allow_dispute_with_negative_available_amount: true,
+            backwards_timestamps: BackwardsTimestampsPolicy::Warn,
+            reorder_window: 1000,
+            dispute_window: TimeLimit::default(),
+            chargeback_deadline: TimeLimit::default(),
+            auto_resolve_expired_disputes: false,
+            max_balance: None
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt::{Display, Formatter};
+
+use crate::domain::Amount;
+use crate::domain::account_module::account::{Account, AccountId};
+use crate::domain::account_module::account_event::{AccountEvent, AccountEventKind};
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum InvariantViolationKind {
+    TotalIsNotNetOfHistory { total: Amount, history_total: Amount },
+    HeldIsNotSumOfOpenDisputes { held: Amount, open_disputes: Amount },
+    NegativeHeld { held: Amount },
+    FrozenAccountChanged
+}
+
+/*
+  Something that should never happen, whatever the input is: it means there's a bug in the engine.
+  The operation has been processed anyway, so the violation refers to the account state right after it.
+*/
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InvariantViolation {
+    pub operation_index: u64,
+    pub account_id: AccountId,
+    pub kind: InvariantViolationKind
+}
+
+impl Display for InvariantViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "operation {} on client {}: ", self.operation_index, self.account_id)?;
+        match &self.kind {
+            InvariantViolationKind::TotalIsNotNetOfHistory { total, history_total } => write!(f, "total {total} is not what the history adds up to {history_total}"),
+            InvariantViolationKind::HeldIsNotSumOfOpenDisputes { held, open_disputes } => write!(f, "held {held} is not the sum of the open disputes {open_disputes}"),
+            InvariantViolationKind::NegativeHeld { held } => write!(f, "held {held} is negative"),
+            InvariantViolationKind::FrozenAccountChanged => write!(f, "a frozen account has changed")
+        }
+    }
+}
+
+/*
+  The balances of an account as its history says they should be, added up from the amounts of its events apart from the account
+  itself: the total is what was deposited minus what was withdrawn or charged back, the held amount what the open disputes are holding.
+  Only the events added since the last time are added up, so that keeping it costs the same for every operation.
+*/
+#[derive(Default)]
+pub struct HistoryBalances {
+    held: Amount,
+    total: Amount,
+    events_added_up: usize
+}
+
+impl HistoryBalances {
+    pub fn add_up_new_events(&mut self, account_history: &[AccountEvent]) {
+        for account_event in &account_history[self.events_added_up..] {
+            let amount = account_event.amount();
+            match account_event.kind() {
+                AccountEventKind::Deposit => self.total += amount,
+                AccountEventKind::Withdrawal => self.total -= amount,
+                AccountEventKind::DisputeOpened => self.held += amount,
+                AccountEventKind::DisputeResolved | AccountEventKind::DisputeExpired => self.held -= amount,
+                AccountEventKind::ChargedBack => {
+                    self.held -= amount;
+                    self.total -= amount;
+                }
+                AccountEventKind::Frozen => {}
+            }
+        }
+        self.events_added_up = account_history.len();
+    }
+}
+
+/*
+  Checks the account right after an operation. The account before the operation, if any, is needed to check that
+  frozen accounts never change; the balances added up from its history to check the held and the total amounts.
+*/
+pub fn check_account_invariants(operation_index: u64, account_before: Option<&Account>, account_after: &Account, history_balances: &HistoryBalances) -> Vec<InvariantViolation> {
+    let available = account_after.available_amount();
+    let held = account_after.held_amount();
+    let total = account_after.total_amount();
+
+    let mut violation_kinds = vec![];
+    if total != history_balances.total {
+        violation_kinds.push(InvariantViolationKind::TotalIsNotNetOfHistory { total, history_total: history_balances.total });
+    }
+    if held != history_balances.held {
+        violation_kinds.push(InvariantViolationKind::HeldIsNotSumOfOpenDisputes { held, open_disputes: history_balances.held });
+    }
+    if held.is_sign_negative() && !held.is_zero() {
+        violation_kinds.push(InvariantViolationKind::NegativeHeld { held });
+    }
+    if let Some(frozen_account_before @ Account::Frozen(_)) = account_before {
+        let unchanged = matches!(account_after, Account::Frozen(_))
+            && frozen_account_before.available_amount() == available
+            && frozen_account_before.held_amount() == held;
+        if !unchanged {
+            violation_kinds.push(InvariantViolationKind::FrozenAccountChanged);
+        }
+    }
+
+    violation_kinds.into_iter()
+        .map(|kind| InvariantViolation { operation_index, account_id: account_after.id(), kind })
+        .collect()
+}
+
+#[cfg(test)]
+mod invariant_check_test;
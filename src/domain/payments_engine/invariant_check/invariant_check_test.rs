@@ -0,0 +1,51 @@
+use crate::domain::Amount;
+use crate::domain::account_module::account::{Account, ActiveAccount};
+use crate::domain::account_module::account_event::{AccountEvent, AccountEventKind};
+use crate::domain::payments_engine::invariant_check::{check_account_invariants, HistoryBalances, InvariantViolationKind};
+use crate::domain::payments_engine::operation_request::OperationReference;
+
+#[test]
+fn test_releasing_more_than_held_is_reported() {
+    let account_after = Account::Active(ActiveAccount::new(1).release_held_amount(Amount::ONE).unwrap());
+
+    let violation_kinds: Vec<InvariantViolationKind> = check_account_invariants(3, None, &account_after, &HistoryBalances::default())
+        .into_iter()
+        .map(|invariant_violation| invariant_violation.kind)
+        .collect();
+
+    assert_eq!(violation_kinds, vec![
+        InvariantViolationKind::HeldIsNotSumOfOpenDisputes { held: Amount::NEGATIVE_ONE, open_disputes: Amount::ZERO },
+        InvariantViolationKind::NegativeHeld { held: Amount::NEGATIVE_ONE },
+    ]);
+}
+
+#[test]
+fn test_a_total_the_history_doesnt_add_up_to_is_reported() {
+    let deposited_account = Account::Active(ActiveAccount::new(1).deposit(Amount::new(5, 0)).unwrap());
+    let account_history = [
+        AccountEvent::new(OperationReference { index: 1, timestamp: None }, AccountEventKind::Deposit, 1, Amount::new(5, 0), &deposited_account),
+        AccountEvent::new(OperationReference { index: 2, timestamp: None }, AccountEventKind::Withdrawal, 2, Amount::new(2, 0), &deposited_account),
+    ];
+    let mut history_balances = HistoryBalances::default();
+    history_balances.add_up_new_events(&account_history[..1]);
+    assert!(check_account_invariants(1, None, &deposited_account, &history_balances).is_empty());
+
+    // The withdrawal is in the history, but the account didn't change
+    history_balances.add_up_new_events(&account_history);
+    let invariant_violations = check_account_invariants(2, None, &deposited_account, &history_balances);
+
+    assert_eq!(invariant_violations.len(), 1);
+    assert_eq!(invariant_violations[0].kind, InvariantViolationKind::TotalIsNotNetOfHistory { total: Amount::new(5, 0), history_total: Amount::new(3, 0) });
+}
+
+#[test]
+fn test_a_frozen_account_that_changes_is_reported() {
+    let account_before = Account::Frozen(ActiveAccount::new(1).charge_back_amount(Amount::ZERO).unwrap());
+    let account_after = Account::Active(ActiveAccount::new(1));
+
+    let invariant_violations = check_account_invariants(3, Some(&account_before), &account_after, &HistoryBalances::default());
+
+    assert_eq!(invariant_violations.len(), 1);
+    assert_eq!(invariant_violations[0].kind, InvariantViolationKind::FrozenAccountChanged);
+    assert_eq!(invariant_violations[0].to_string(), "operation 3 on client 1: a frozen account has changed");
+}
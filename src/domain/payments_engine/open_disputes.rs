@@ -0,0 +1,45 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use crate::domain::Timestamp;
+use crate::domain::payments_engine::engine_policy::TimeLimit;
+use crate::domain::payments_engine::operation_request::OperationReference;
+use crate::domain::transaction_module::transaction::TransactionId;
+
+/*
+  The disputes opened so far, with the operation that opened them, in the order they expire.
+  A dispute with a timestamp expires by time when the deadline has a time limit (the engine clock has a timestamp from then on),
+  any other one by operations: each kind has its own queue, sorted by when the dispute was opened, so that the first dispute
+  of each queue is the first one to expire, whatever order the timestamps arrived in.
+*/
+#[derive(Default)]
+pub struct OpenDisputes {
+    by_time: BinaryHeap<Reverse<(Timestamp, u64, TransactionId)>>,
+    by_operations: VecDeque<(TransactionId, OperationReference)>
+}
+
+impl OpenDisputes {
+    pub fn push(&mut self, transaction_id: TransactionId, dispute_operation: OperationReference, deadline: &TimeLimit) {
+        match dispute_operation.timestamp {
+            Some(timestamp) if deadline.max_seconds.is_some() => self.by_time.push(Reverse((timestamp, dispute_operation.index, transaction_id))),
+            _ => self.by_operations.push_back((transaction_id, dispute_operation))
+        }
+    }
+
+    /*
+      Removes and returns the first dispute of either queue for which is_done holds (expired, or not open anymore),
+      None when the first dispute of both queues is still pending.
+    */
+    pub fn pop_first_done(&mut self, mut is_done: impl FnMut(TransactionId, OperationReference) -> bool) -> Option<(TransactionId, OperationReference)> {
+        if let Some(&Reverse((timestamp, index, transaction_id))) = self.by_time.peek() {
+            let dispute_operation = OperationReference { index, timestamp: Some(timestamp) };
+            if is_done(transaction_id, dispute_operation) {
+                self.by_time.pop();
+                return Some((transaction_id, dispute_operation));
+            }
+        }
+        match self.by_operations.front() {
+            Some(&(transaction_id, dispute_operation)) if is_done(transaction_id, dispute_operation) => self.by_operations.pop_front(),
+            _ => None
+        }
+    }
+}
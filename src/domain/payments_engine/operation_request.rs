@@ -1,8 +1,12 @@
-use crate::domain::Amount;
+use crate::domain::{Amount, Timestamp};
 use crate::domain::account_module::account::AccountId;
 use crate::domain::transaction_module::transaction::TransactionId;
-use crate::use_case::process_from_csv_use_case::{CsvOperationType, OperationCsvRow};
 
+/*
+  The only input accepted by the PaymentsEngine. It doesn't know anything about where the operation comes from:
+  every input format (CSV, JSON Lines, HTTP, ...) is responsible for converting its rows into an OperationRequest.
+  The timestamp is optional: without it, the order of the operations is just the order they are submitted in.
+*/
 #[derive(Clone)]
 pub enum OperationRequest {
     Deposit(DepositOperationRequest),
@@ -12,73 +16,84 @@ pub enum OperationRequest {
     Chargeback(ChargebackOperationRequest)
 }
 
+/*
+  An operation the engine has processed: its index is its position among all the operations submitted to the engine,
+  so events of different accounts can be put back in order and the distance between two operations can be measured even without timestamps.
+*/
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct OperationReference {
+    pub index: u64,
+    pub timestamp: Option<Timestamp>
+}
+
 impl OperationRequest {
-    pub fn new_from_csv(operation_csv_row: OperationCsvRow) -> Result<Self, String> {
-        match operation_csv_row.operation_type {
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        match self {
+            OperationRequest::Deposit(deposit_operation_request) => deposit_operation_request.timestamp,
+            OperationRequest::Withdrawal(withdrawal_operation_request) => withdrawal_operation_request.timestamp,
+            OperationRequest::Dispute(dispute_operation_request) => dispute_operation_request.timestamp,
+            OperationRequest::Resolve(resolve_operation_request) => resolve_operation_request.timestamp,
+            OperationRequest::Chargeback(chargeback_operation_request) => chargeback_operation_request.timestamp
+        }
+    }
 
-            CsvOperationType::Deposit => {
-                if let Some(amount) = operation_csv_row.amount {
-                    Ok(OperationRequest::Deposit(DepositOperationRequest {
-                        account_id: operation_csv_row.client,
-                        transaction_id: operation_csv_row.tx,
-                        amount: amount.trunc_with_scale(4)
-                    }))
-                } else {
-                    Err("Amount not found for Deposit transaction_module request".to_owned())
-                }
-            }
-            CsvOperationType::Withdrawal => {
-                if let Some(amount) = operation_csv_row.amount {
-                    Ok(OperationRequest::Withdrawal(WithdrawalOperationRequest {
-                        account_id: operation_csv_row.client,
-                        transaction_id: operation_csv_row.tx,
-                        amount: amount.trunc_with_scale(4)
-                    }))
-                } else {
-                    Err("Amount not found for Withdrawal transaction_module request".to_owned())
-                }
-            }
-            CsvOperationType::Dispute => {
-                Ok(OperationRequest::Dispute(DisputeOperationRequest{transaction_id: operation_csv_row.tx}))
-            }
-            CsvOperationType::Resolve => {
-                Ok(OperationRequest::Resolve(ResolveOperationRequest{transaction_id: operation_csv_row.tx}))
-            }
-            CsvOperationType::Chargeback => {
-                Ok(OperationRequest::Chargeback(ChargebackOperationRequest{transaction_id: operation_csv_row.tx}))
-            }
+    /*
+      The name of the operation, spelled like in every input format.
+    */
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            OperationRequest::Deposit(_) => "deposit",
+            OperationRequest::Withdrawal(_) => "withdrawal",
+            OperationRequest::Dispute(_) => "dispute",
+            OperationRequest::Resolve(_) => "resolve",
+            OperationRequest::Chargeback(_) => "chargeback"
         }
     }
 
+    /*
+      The new transaction of a deposit or a withdrawal, the referenced one otherwise.
+    */
+    pub fn transaction_id(&self) -> TransactionId {
+        match self {
+            OperationRequest::Deposit(deposit_operation_request) => deposit_operation_request.transaction_id,
+            OperationRequest::Withdrawal(withdrawal_operation_request) => withdrawal_operation_request.transaction_id,
+            OperationRequest::Dispute(dispute_operation_request) => dispute_operation_request.transaction_id,
+            OperationRequest::Resolve(resolve_operation_request) => resolve_operation_request.transaction_id,
+            OperationRequest::Chargeback(chargeback_operation_request) => chargeback_operation_request.transaction_id
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct DepositOperationRequest {
     pub account_id: AccountId,
     pub transaction_id: TransactionId,
-    pub amount: Amount
+    pub amount: Amount,
+    pub timestamp: Option<Timestamp>
 }
 
 #[derive(Clone)]
 pub struct WithdrawalOperationRequest {
     pub account_id: AccountId,
     pub transaction_id: TransactionId,
-    pub amount: Amount
+    pub amount: Amount,
+    pub timestamp: Option<Timestamp>
 }
 
 #[derive(Clone)]
 pub struct DisputeOperationRequest {
-    pub transaction_id: TransactionId
+    pub transaction_id: TransactionId,
+    pub timestamp: Option<Timestamp>
 }
 
 #[derive(Clone)]
 pub struct ResolveOperationRequest {
-    pub transaction_id: TransactionId
+    pub transaction_id: TransactionId,
+    pub timestamp: Option<Timestamp>
 }
 
 #[derive(Clone)]
 pub struct ChargebackOperationRequest {
-    pub transaction_id: TransactionId
+    pub transaction_id: TransactionId,
+    pub timestamp: Option<Timestamp>
 }
-
-
@@ -0,0 +1,91 @@
+use crate::domain::{Amount, Timestamp};
+use crate::domain::account_module::account_event::AccountEventKind;
+use crate::domain::payments_engine::engine_policy::{EnginePolicy, TimeLimit};
+use crate::domain::payments_engine::{OperationValidationError, PaymentsEngine};
+use crate::domain::payments_engine::operation_request::{DepositOperationRequest, DisputeOperationRequest, OperationRequest, WithdrawalOperationRequest};
+
+fn deposit(account_id: u16, transaction_id: u32, amount: Amount) -> OperationRequest {
+    OperationRequest::Deposit(DepositOperationRequest { account_id, transaction_id, amount, timestamp: None })
+}
+
+fn withdrawal(account_id: u16, transaction_id: u32, amount: Amount) -> OperationRequest {
+    OperationRequest::Withdrawal(WithdrawalOperationRequest { account_id, transaction_id, amount, timestamp: None })
+}
+
+#[test]
+fn test_withdrawals_over_the_available_amount_are_rejected_without_consuming_their_id() {
+    let mut payments_engine = PaymentsEngine::new();
+    payments_engine.process(deposit(1, 1, Amount::new(5, 0))).unwrap();
+
+    assert_eq!(payments_engine.process(withdrawal(1, 2, Amount::new(6, 0))), Err(vec![OperationValidationError::NotEnoughAvailableAmount]));
+    assert_eq!(payments_engine.account(1).unwrap().available_amount(), Amount::new(5, 0));
+    assert!(payments_engine.transaction(2).is_none());
+    assert_eq!(payments_engine.account_history(1).len(), 1);
+
+    assert_eq!(payments_engine.process(withdrawal(1, 2, Amount::new(5, 0))), Ok(()));
+    assert_eq!(payments_engine.account(1).unwrap().available_amount(), Amount::ZERO);
+}
+
+#[test]
+fn test_withdrawals_cant_use_the_held_amount() {
+    let mut payments_engine = PaymentsEngine::new();
+    payments_engine.process(deposit(1, 1, Amount::new(10, 0))).unwrap();
+    payments_engine.process(deposit(1, 2, Amount::new(5, 0))).unwrap();
+    payments_engine.process(OperationRequest::Dispute(DisputeOperationRequest { transaction_id: 1, timestamp: None })).unwrap();
+
+    assert_eq!(payments_engine.process(withdrawal(1, 3, Amount::new(6, 0))), Err(vec![OperationValidationError::NotEnoughAvailableAmount]));
+    assert_eq!(payments_engine.account(1).unwrap().total_amount(), Amount::new(15, 0));
+}
+
+#[test]
+fn test_a_balance_past_the_max_is_reported_with_the_other_errors() {
+    let mut payments_engine = PaymentsEngine::with_policy(EnginePolicy { max_balance: Some(Amount::new(10, 0)), ..EnginePolicy::default() });
+    payments_engine.process(deposit(1, 1, Amount::new(8, 0))).unwrap();
+
+    assert_eq!(payments_engine.process(deposit(1, 1, Amount::new(5, 0))), Err(vec![OperationValidationError::TransactionIdAlreadyExisting, OperationValidationError::BalanceOverflow]));
+    assert_eq!(payments_engine.process(deposit(1, 2, Amount::new(5, 0))), Err(vec![OperationValidationError::BalanceOverflow]));
+    assert_eq!(payments_engine.account(1).unwrap().available_amount(), Amount::new(8, 0));
+}
+
+#[test]
+fn test_validating_gives_the_outcome_of_processing_without_changing_anything() {
+    let mut payments_engine = PaymentsEngine::new();
+    payments_engine.process(deposit(1, 1, Amount::new(5, 0))).unwrap();
+
+    let operation_requests_and_outcomes = [
+        (deposit(1, 1, Amount::ONE), Err(vec![OperationValidationError::TransactionIdAlreadyExisting])),
+        (withdrawal(1, 2, Amount::new(6, 0)), Err(vec![OperationValidationError::NotEnoughAvailableAmount])),
+        (withdrawal(1, 2, Amount::new(5, 0)), Ok(())),
+    ];
+    for (operation_request, expected_outcome) in &operation_requests_and_outcomes {
+        assert_eq!(&payments_engine.validate(operation_request), expected_outcome);
+    }
+    assert_eq!(payments_engine.account(1).unwrap().available_amount(), Amount::new(5, 0));
+    assert_eq!(payments_engine.account_history(1).len(), 1);
+
+    for (operation_request, expected_outcome) in operation_requests_and_outcomes {
+        assert_eq!(payments_engine.process(operation_request), expected_outcome);
+    }
+}
+
+#[test]
+fn test_a_backwards_timestamp_doesnt_keep_expired_disputes_open() {
+    let engine_policy = EnginePolicy {
+        chargeback_deadline: TimeLimit { max_seconds: Some(3600), max_operations: None },
+        auto_resolve_expired_disputes: true,
+        ..EnginePolicy::default()
+    };
+    let mut payments_engine = PaymentsEngine::with_policy(engine_policy);
+    let timestamp = |time: &str| Some(format!("2024-03-01T{time}Z").parse::<Timestamp>().unwrap());
+
+    payments_engine.process(OperationRequest::Deposit(DepositOperationRequest { account_id: 1, transaction_id: 1, amount: Amount::new(10, 0), timestamp: timestamp("10:00:00") })).unwrap();
+    payments_engine.process(OperationRequest::Deposit(DepositOperationRequest { account_id: 2, transaction_id: 2, amount: Amount::new(1, 0), timestamp: timestamp("11:30:00") })).unwrap();
+    // Its deadline, 11:00, is already past when it's opened
+    payments_engine.process(OperationRequest::Dispute(DisputeOperationRequest { transaction_id: 1, timestamp: timestamp("10:00:00") })).unwrap();
+
+    assert_eq!(payments_engine.account(1).unwrap().held_amount(), Amount::ZERO);
+    let last_account_event = payments_engine.account_history(1).last().unwrap();
+    assert_eq!(last_account_event.kind(), AccountEventKind::DisputeExpired);
+    assert_eq!(last_account_event.operation_index(), 3);
+    assert_eq!(last_account_event.timestamp(), timestamp("11:30:00"));
+}
@@ -1,31 +1,50 @@
 mod validator;
 mod builder;
 
-use crate::domain::account_module::account::ActiveAccount;
-use crate::domain::account_module::account_repository::AccountRepository;
-use crate::domain::payments_engine::operation_request::OperationRequest;
+use crate::domain::Timestamp;
+use crate::domain::account_module::account::{Account, ActiveAccount, FrozenAccount};
+use crate::domain::payments_engine::engine_policy::EnginePolicy;
+use crate::domain::payments_engine::operation_request::{OperationReference, OperationRequest};
+use crate::domain::payments_engine::valid_operation_request::validator::validate_timestamp_order;
 use crate::domain::payments_engine::valid_operation_request::builder::{build_chargeback, build_deposit, build_dispute, build_resolve, build_withdrawal};
-use crate::domain::transaction_module::transaction::{DepositTransaction, DisputedDepositTransaction, WithdrawalTransaction};
-use crate::domain::transaction_module::transaction_repository::TransactionRepository;
-use crate::domain::payments_engine::valid_operation_request::validator::OperationValidationError;
+use crate::domain::transaction_module::transaction::{ChargedBackDepositTransaction, DepositTransaction, DisputedDepositTransaction, Transaction, WithdrawalTransaction};
+pub use crate::domain::payments_engine::valid_operation_request::validator::OperationValidationError;
 
+/*
+  Every variant carries the transaction and the account as they are after the operation, already computed (and checked for overflows)
+  by the validation: applying it is just storing them, in place of the ones it was validated against.
+*/
 pub enum ValidOperationRequest {
-    Deposit { new_transaction: DepositTransaction, to_account: ActiveAccount },
-    Withdrawal { new_transaction: WithdrawalTransaction, from_account: ActiveAccount },
-    OpenDispute { on_transaction: DepositTransaction, account: ActiveAccount },
-    ResolveDispute { on_transaction: DisputedDepositTransaction, account: ActiveAccount },
-    ChargeBack { on_transaction: DisputedDepositTransaction, account: ActiveAccount },
+    Deposit { new_transaction: DepositTransaction, updated_account: ActiveAccount },
+    Withdrawal { new_transaction: WithdrawalTransaction, updated_account: ActiveAccount },
+    OpenDispute { disputed_transaction: DisputedDepositTransaction, updated_account: ActiveAccount },
+    ResolveDispute { resolved_transaction: DepositTransaction, updated_account: ActiveAccount },
+    ChargeBack { charged_back_transaction: ChargedBackDepositTransaction, updated_account: FrozenAccount },
 }
 
 impl ValidOperationRequest {
 
-    pub fn new(operation_request: &OperationRequest, account_repository: &AccountRepository, transaction_repository: &TransactionRepository) -> Result<Self, Vec<OperationValidationError>> {
-        match operation_request {
-            OperationRequest::Deposit(deposit_operation_request) => build_deposit(deposit_operation_request, account_repository, transaction_repository),
-            OperationRequest::Withdrawal(withdrawal_operation_request) => build_withdrawal(withdrawal_operation_request, account_repository, transaction_repository),
-            OperationRequest::Dispute(dispute_operation_request) => build_dispute(dispute_operation_request, account_repository, transaction_repository),
-            OperationRequest::Resolve(resolve_operation_request) => build_resolve(resolve_operation_request, account_repository, transaction_repository),
-            OperationRequest::Chargeback(chargeback_operation_request) => build_chargeback(chargeback_operation_request, account_repository, transaction_repository)
+    /*
+      The timestamp order is checked on top of the validations of the specific operation, so that every error is reported at once.
+      maybe_transaction is the one with the transaction id of the request, maybe_account the one the operation is on (see PaymentsEngine::account_id_of).
+    */
+    pub fn new(operation_request: &OperationRequest, operation: OperationReference, maybe_latest_timestamp: Option<Timestamp>, maybe_account: Option<&Account>, maybe_transaction: Option<&Transaction>, engine_policy: &EnginePolicy) -> Result<Self, Vec<OperationValidationError>> {
+        let validated_timestamp_result = validate_timestamp_order(operation.timestamp, maybe_latest_timestamp, engine_policy.backwards_timestamps);
+        let valid_operation_request_result = match operation_request {
+            OperationRequest::Deposit(deposit_operation_request) => build_deposit(deposit_operation_request, operation, maybe_account, maybe_transaction, engine_policy),
+            OperationRequest::Withdrawal(withdrawal_operation_request) => build_withdrawal(withdrawal_operation_request, operation, maybe_account, maybe_transaction, engine_policy),
+            OperationRequest::Dispute(_) => build_dispute(operation, maybe_account, maybe_transaction, engine_policy),
+            OperationRequest::Resolve(_) => build_resolve(maybe_account, maybe_transaction, engine_policy),
+            OperationRequest::Chargeback(_) => build_chargeback(operation, maybe_account, maybe_transaction, engine_policy)
+        };
+
+        match (validated_timestamp_result, valid_operation_request_result) {
+            (Ok(_), valid_operation_request_result) => valid_operation_request_result,
+            (Err(error), Ok(_)) => Err(vec![error]),
+            (Err(error), Err(mut errors)) => {
+                errors.push(error);
+                Err(errors)
+            }
         }
     }
 }
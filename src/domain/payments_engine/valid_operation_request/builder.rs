@@ -1,29 +1,32 @@
 use crate::domain::account_module::account::{Account, ActiveAccount};
-use crate::domain::account_module::account_repository::AccountRepository;
-use crate::domain::payments_engine::operation_request::{ChargebackOperationRequest, DepositOperationRequest, DisputeOperationRequest, ResolveOperationRequest, WithdrawalOperationRequest};
-use crate::domain::payments_engine::valid_operation_request::validator::{validate_existing_and_active_account, validate_existing_transaction, validate_positive_amount, validate_transaction_is_deposit, validate_transaction_is_disputed_deposit, validate_unique_transaction_id, OperationValidationError};
+use crate::domain::payments_engine::engine_policy::EnginePolicy;
+use crate::domain::payments_engine::operation_request::{OperationReference, DepositOperationRequest, WithdrawalOperationRequest};
+use crate::domain::payments_engine::valid_operation_request::validator::{validate_balance_limit, validate_existing_and_active_account, validate_enough_available_amount, validate_existing_transaction, validate_positive_amount, validate_transaction_is_deposit, validate_transaction_is_disputed_deposit, validate_unique_transaction_id, validate_chargeback_deadline, validate_dispute_window, OperationValidationError};
 use crate::domain::payments_engine::valid_operation_request::ValidOperationRequest;
-use crate::domain::transaction_module::transaction::{DepositTransaction, WithdrawalTransaction};
-use crate::domain::transaction_module::transaction_repository::TransactionRepository;
+use crate::domain::transaction_module::transaction::{DepositTransaction, Transaction, WithdrawalTransaction};
 
 /*
   The approach is simple: errors are collected in the vec[], and if there is at least one, then return an Err, otherwise an Ok with
   the correct type.
   The error collecting could have been done in a more elegant way by implementing manually various "compose" functions for different number of arguments - something
   already present in Scala (cats) or Kotlin (arrow) libraries
+  The builders don't look anything up: they get the account and the transaction the operation is on (when they exist), already found by the engine.
+  Each one has a trace span, inside the one of the operation, with what it was given.
 */
 
-pub fn build_deposit(deposit_operation_request: &DepositOperationRequest, account_repository: &AccountRepository, transaction_repository: &TransactionRepository) -> Result<ValidOperationRequest, Vec<OperationValidationError>>  {
+#[tracing::instrument(level = "trace", skip_all, fields(client = deposit_operation_request.account_id, tx = deposit_operation_request.transaction_id, amount = %deposit_operation_request.amount, account_found = maybe_account.is_some()))]
+pub fn build_deposit(deposit_operation_request: &DepositOperationRequest, operation: OperationReference, maybe_account: Option<&Account>, maybe_existing_transaction: Option<&Transaction>, engine_policy: &EnginePolicy) -> Result<ValidOperationRequest, Vec<OperationValidationError>>  {
     let mut errors: Vec<OperationValidationError> = vec![];
 
     let default_new_account = Account::Active(ActiveAccount::new(deposit_operation_request.account_id));
-    let account = account_repository
-        .find(deposit_operation_request.account_id)
-        .unwrap_or(&default_new_account);
+    let account = maybe_account.unwrap_or(&default_new_account);
 
     let validated_account_result = validate_existing_and_active_account(Some(account));
     let validated_amount_result = validate_positive_amount(deposit_operation_request.amount);
-    let validated_transaction_id = validate_unique_transaction_id(deposit_operation_request.transaction_id, transaction_repository);
+    let validated_transaction_id = validate_unique_transaction_id(deposit_operation_request.transaction_id, maybe_existing_transaction);
+    // Only when there's an account and an amount to compute the balances with
+    let validated_updated_account_result = validated_account_result.clone().ok().zip(validated_amount_result.clone().ok())
+        .map(|(account, amount)| validate_balance_limit(account.deposit(amount), engine_policy.max_balance));
 
     if let Err(error) = validated_account_result.clone() {
         errors.push(error)
@@ -34,24 +37,33 @@ pub fn build_deposit(deposit_operation_request: &DepositOperationRequest, accoun
     if let Err(error) = validated_transaction_id.clone() {
         errors.push(error)
     }
+    if let Some(Err(error)) = validated_updated_account_result.clone() {
+        errors.push(error)
+    }
 
-    match (validated_account_result, validated_amount_result, validated_transaction_id) {
-        (Ok(account), Ok(amount), Ok(transaction_id)) => {
-            let new_transaction = DepositTransaction::new(transaction_id, account.id(), amount);
-            Ok(ValidOperationRequest::Deposit { new_transaction, to_account: account.clone() })
+    match (validated_account_result, validated_amount_result, validated_transaction_id, validated_updated_account_result) {
+        (Ok(account), Ok(amount), Ok(transaction_id), Some(Ok(updated_account))) => {
+            let new_transaction = DepositTransaction::new(transaction_id, account.id(), amount, operation);
+            Ok(ValidOperationRequest::Deposit { new_transaction, updated_account })
         },
         _ => Err(errors)
     }
 }
 
-pub fn build_withdrawal(withdrawal_operation_request: &WithdrawalOperationRequest, account_repository: &AccountRepository, transaction_repository: &TransactionRepository) -> Result<ValidOperationRequest, Vec<OperationValidationError>>  {
+#[tracing::instrument(level = "trace", skip_all, fields(client = withdrawal_operation_request.account_id, tx = withdrawal_operation_request.transaction_id, amount = %withdrawal_operation_request.amount, account_found = maybe_account.is_some()))]
+pub fn build_withdrawal(withdrawal_operation_request: &WithdrawalOperationRequest, operation: OperationReference, maybe_account: Option<&Account>, maybe_existing_transaction: Option<&Transaction>, engine_policy: &EnginePolicy) -> Result<ValidOperationRequest, Vec<OperationValidationError>>  {
     let mut errors: Vec<OperationValidationError> = vec![];
 
-    let maybe_account = account_repository.find(withdrawal_operation_request.account_id);
-
     let validated_account_result = validate_existing_and_active_account(maybe_account);
-    let validated_amount_result = validate_positive_amount(withdrawal_operation_request.amount);
-    let validated_transaction_id = validate_unique_transaction_id(withdrawal_operation_request.transaction_id, transaction_repository);
+    let validated_amount_result = validate_positive_amount(withdrawal_operation_request.amount)
+        .and_then(|amount| match validated_account_result {
+            Ok(account) => validate_enough_available_amount(account, amount),
+            Err(_) => Ok(amount)
+        });
+    let validated_transaction_id = validate_unique_transaction_id(withdrawal_operation_request.transaction_id, maybe_existing_transaction);
+    // Only when there's an account and an amount to compute the balances with
+    let validated_updated_account_result = validated_account_result.clone().ok().zip(validated_amount_result.clone().ok())
+        .map(|(account, amount)| validate_balance_limit(account.withdraw(amount), engine_policy.max_balance));
 
     if let Err(error) = validated_account_result.clone() {
         errors.push(error)
@@ -62,24 +74,38 @@ pub fn build_withdrawal(withdrawal_operation_request: &WithdrawalOperationReques
     if let Err(error) = validated_transaction_id.clone() {
         errors.push(error)
     }
+    if let Some(Err(error)) = validated_updated_account_result.clone() {
+        errors.push(error)
+    }
 
-    match (validated_account_result, validated_amount_result, validated_transaction_id) {
-        (Ok(account), Ok(amount), Ok(transaction_id)) => {
-            let new_transaction = WithdrawalTransaction::new(transaction_id, account.id(), amount);
-            Ok(ValidOperationRequest::Withdrawal { new_transaction, from_account: account.clone() })
+    match (validated_account_result, validated_amount_result, validated_transaction_id, validated_updated_account_result) {
+        (Ok(account), Ok(amount), Ok(transaction_id), Some(Ok(updated_account))) => {
+            let new_transaction = WithdrawalTransaction::new(transaction_id, account.id(), amount, operation);
+            Ok(ValidOperationRequest::Withdrawal { new_transaction, updated_account })
         },
         _ => Err(errors)
     }
 }
 
-pub fn build_dispute(dispute_operation_request: &DisputeOperationRequest, account_repository: &AccountRepository, transaction_repository: &TransactionRepository) -> Result<ValidOperationRequest, Vec<OperationValidationError>> {
+#[tracing::instrument(level = "trace", skip_all, fields(client = maybe_account.map(Account::id), tx = maybe_transaction.map(Transaction::id)))]
+pub fn build_dispute(operation: OperationReference, maybe_account: Option<&Account>, maybe_transaction: Option<&Transaction>, engine_policy: &EnginePolicy) -> Result<ValidOperationRequest, Vec<OperationValidationError>> {
     let mut errors: Vec<OperationValidationError> = vec![];
 
-    let validated_transaction_and_account_result = validate_existing_transaction(dispute_operation_request.transaction_id, transaction_repository)
+    let validated_transaction_and_account_result = validate_existing_transaction(maybe_transaction)
         .and_then(validate_transaction_is_deposit)
+        .and_then(|deposit_transaction| validate_dispute_window(deposit_transaction, operation, &engine_policy.dispute_window).map(|_| deposit_transaction))
         .and_then(|deposit_transaction| {
-            let maybe_account = account_repository.find(deposit_transaction.to_account_id());
             validate_existing_and_active_account(maybe_account).map (|active_account| (deposit_transaction, active_account))
+        })
+        .and_then(|(deposit_transaction, active_account)| {
+            if engine_policy.allow_dispute_with_negative_available_amount {
+                Ok((deposit_transaction, active_account))
+            } else {
+                validate_enough_available_amount(active_account, deposit_transaction.amount()).map(|_| (deposit_transaction, active_account))
+            }
+        })
+        .and_then(|(deposit_transaction, active_account)| {
+            validate_balance_limit(active_account.hold_amount(deposit_transaction.amount()), engine_policy.max_balance).map(|updated_account| (deposit_transaction, updated_account))
         });
 
 
@@ -88,21 +114,24 @@ pub fn build_dispute(dispute_operation_request: &DisputeOperationRequest, accoun
     }
 
     match validated_transaction_and_account_result {
-        Ok((deposit_transaction, active_account)) => {
-            Ok(ValidOperationRequest::OpenDispute { on_transaction: deposit_transaction.clone(), account: active_account.clone() })
+        Ok((deposit_transaction, updated_account)) => {
+            Ok(ValidOperationRequest::OpenDispute { disputed_transaction: deposit_transaction.open_dispute(operation), updated_account })
         },
         _ => Err(errors)
     }
 }
 
-pub fn build_resolve(resolve_operation_request: &ResolveOperationRequest, account_repository: &AccountRepository, transaction_repository: &TransactionRepository) -> Result<ValidOperationRequest, Vec<OperationValidationError>> {
+#[tracing::instrument(level = "trace", skip_all, fields(client = maybe_account.map(Account::id), tx = maybe_transaction.map(Transaction::id)))]
+pub fn build_resolve(maybe_account: Option<&Account>, maybe_transaction: Option<&Transaction>, engine_policy: &EnginePolicy) -> Result<ValidOperationRequest, Vec<OperationValidationError>> {
     let mut errors: Vec<OperationValidationError> = vec![];
 
-    let validated_transaction_and_account_result = validate_existing_transaction(resolve_operation_request.transaction_id, transaction_repository)
+    let validated_transaction_and_account_result = validate_existing_transaction(maybe_transaction)
         .and_then(validate_transaction_is_disputed_deposit)
         .and_then(|disputed_deposit_transaction| {
-            let maybe_account = account_repository.find(disputed_deposit_transaction.to_account_id());
             validate_existing_and_active_account(maybe_account).map (|active_account| (disputed_deposit_transaction, active_account))
+        })
+        .and_then(|(disputed_deposit_transaction, active_account)| {
+            validate_balance_limit(active_account.release_held_amount(disputed_deposit_transaction.amount()), engine_policy.max_balance).map(|updated_account| (disputed_deposit_transaction, updated_account))
         });
 
 
@@ -111,21 +140,28 @@ pub fn build_resolve(resolve_operation_request: &ResolveOperationRequest, accoun
     }
 
     match validated_transaction_and_account_result {
-        Ok((disputed_deposit_transaction, active_account)) => {
-            Ok(ValidOperationRequest::ResolveDispute { on_transaction: disputed_deposit_transaction.clone(), account: active_account.clone() })
+        Ok((disputed_deposit_transaction, updated_account)) => {
+            Ok(ValidOperationRequest::ResolveDispute { resolved_transaction: disputed_deposit_transaction.resolve_dispute(), updated_account })
         }
         _ => Err(errors)
     }
 }
 
-pub fn build_chargeback(chargeback_operation_request: &ChargebackOperationRequest, account_repository: &AccountRepository, transaction_repository: &TransactionRepository) -> Result<ValidOperationRequest, Vec<OperationValidationError>> {
+#[tracing::instrument(level = "trace", skip_all, fields(client = maybe_account.map(Account::id), tx = maybe_transaction.map(Transaction::id)))]
+pub fn build_chargeback(operation: OperationReference, maybe_account: Option<&Account>, maybe_transaction: Option<&Transaction>, engine_policy: &EnginePolicy) -> Result<ValidOperationRequest, Vec<OperationValidationError>> {
     let mut errors: Vec<OperationValidationError> = vec![];
 
-    let validated_transaction_and_account_result = validate_existing_transaction(chargeback_operation_request.transaction_id, transaction_repository)
+    let validated_transaction_and_account_result = validate_existing_transaction(maybe_transaction)
         .and_then(validate_transaction_is_disputed_deposit)
+        .and_then(|disputed_deposit_transaction| validate_chargeback_deadline(disputed_deposit_transaction, operation, &engine_policy.chargeback_deadline).map(|_| disputed_deposit_transaction))
         .and_then(|disputed_deposit_transaction| {
-            let maybe_account = account_repository.find(disputed_deposit_transaction.to_account_id());
             validate_existing_and_active_account(maybe_account).map (|active_account| (disputed_deposit_transaction, active_account))
+        })
+        .and_then(|(disputed_deposit_transaction, active_account)| {
+            // A chargeback only takes money away, so only the overflow matters
+            active_account.charge_back_amount(disputed_deposit_transaction.amount())
+                .ok_or(OperationValidationError::BalanceOverflow)
+                .map(|updated_account| (disputed_deposit_transaction, updated_account))
         });
 
 
@@ -134,8 +170,8 @@ pub fn build_chargeback(chargeback_operation_request: &ChargebackOperationReques
     }
 
     match validated_transaction_and_account_result {
-        Ok((disputed_deposit_transaction, active_account)) => {
-            Ok(ValidOperationRequest::ChargeBack { on_transaction: disputed_deposit_transaction.clone(), account: active_account.clone() })
+        Ok((disputed_deposit_transaction, updated_account)) => {
+            Ok(ValidOperationRequest::ChargeBack { charged_back_transaction: disputed_deposit_transaction.charge_back(), updated_account })
         }
         _ => Err(errors)
     }
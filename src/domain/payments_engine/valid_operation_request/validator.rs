@@ -1,7 +1,8 @@
 use crate::domain::account_module::account::{Account, ActiveAccount};
-use crate::domain::Amount;
+use crate::domain::{Amount, Timestamp};
+use crate::domain::payments_engine::engine_policy::{BackwardsTimestampsPolicy, TimeLimit};
+use crate::domain::payments_engine::operation_request::OperationReference;
 use crate::domain::transaction_module::transaction::{DepositTransaction, DisputedDepositTransaction, Transaction, TransactionId};
-use crate::domain::transaction_module::transaction_repository::TransactionRepository;
 
 pub fn validate_existing_and_active_account(maybe_account: Option<&Account>) -> Result<&ActiveAccount, OperationValidationError> {
     if let Some(account) = maybe_account {
@@ -22,18 +23,30 @@ pub fn validate_positive_amount(amount: Amount) -> Result<Amount, OperationValid
     }
 }
 
-pub fn validate_unique_transaction_id(transaction_id: TransactionId, transaction_repository: &TransactionRepository) -> Result<TransactionId, OperationValidationError> {
+/*
+  A withdrawal over the available amount is rejected at validation time, like any other invalid operation: it's reported,
+  the account is left as it is and its transaction id is not taken. The held amount can't be withdrawn.
+*/
+pub fn validate_enough_available_amount(account: &ActiveAccount, amount: Amount) -> Result<Amount, OperationValidationError> {
+    if account.available_amount() < amount {
+        Err(OperationValidationError::NotEnoughAvailableAmount)
+    } else {
+        Ok(amount)
+    }
+}
+
+pub fn validate_unique_transaction_id(transaction_id: TransactionId, maybe_existing_transaction: Option<&Transaction>) -> Result<TransactionId, OperationValidationError> {
 
-    if transaction_repository.find(transaction_id).is_some() {
+    if maybe_existing_transaction.is_some() {
         Err(OperationValidationError::TransactionIdAlreadyExisting)
     } else {
         Ok(transaction_id)
     }
 }
 
-pub fn validate_existing_transaction(transaction_id: TransactionId, transaction_repository: &TransactionRepository) -> Result<&Transaction, OperationValidationError> {
+pub fn validate_existing_transaction(maybe_transaction: Option<&Transaction>) -> Result<&Transaction, OperationValidationError> {
 
-    if let Some(transaction) = transaction_repository.find(transaction_id) {
+    if let Some(transaction) = maybe_transaction {
         Ok(transaction)
     } else {
         Err(OperationValidationError::TransactionNotFound)
@@ -54,15 +67,62 @@ pub fn validate_transaction_is_disputed_deposit(transaction: &Transaction) -> Re
     }
 }
 
-#[derive(Clone)]
+pub fn validate_timestamp_order(maybe_timestamp: Option<Timestamp>, maybe_latest_timestamp: Option<Timestamp>, backwards_timestamps_policy: BackwardsTimestampsPolicy) -> Result<Option<Timestamp>, OperationValidationError> {
+    match (maybe_timestamp, maybe_latest_timestamp) {
+        (Some(timestamp), Some(latest_timestamp)) if timestamp < latest_timestamp => match backwards_timestamps_policy {
+            BackwardsTimestampsPolicy::Warn => {
+                tracing::warn!(%timestamp, %latest_timestamp, "Operation timestamp is before the latest processed one");
+                Ok(maybe_timestamp)
+            }
+            BackwardsTimestampsPolicy::Reject | BackwardsTimestampsPolicy::Reorder => Err(OperationValidationError::TimestampOutOfOrder)
+        },
+        _ => Ok(maybe_timestamp)
+    }
+}
+
+pub fn validate_dispute_window(deposit_transaction: &DepositTransaction, dispute_operation: OperationReference, dispute_window: &TimeLimit) -> Result<(), OperationValidationError> {
+    if dispute_window.is_expired(deposit_transaction.operation(), dispute_operation) {
+        Err(OperationValidationError::DisputeWindowExpired)
+    } else {
+        Ok(())
+    }
+}
+
+pub fn validate_chargeback_deadline(disputed_deposit_transaction: &DisputedDepositTransaction, chargeback_operation: OperationReference, chargeback_deadline: &TimeLimit) -> Result<(), OperationValidationError> {
+    if chargeback_deadline.is_expired(disputed_deposit_transaction.dispute_operation(), chargeback_operation) {
+        Err(OperationValidationError::ChargebackDeadlineExpired)
+    } else {
+        Ok(())
+    }
+}
+
+/*
+  The account after the operation: None when a balance overflowed while computing it.
+*/
+pub fn validate_balance_limit(maybe_updated_account: Option<ActiveAccount>, max_balance: Option<Amount>) -> Result<ActiveAccount, OperationValidationError> {
+    match (maybe_updated_account, max_balance) {
+        (Some(updated_account), Some(max_balance)) if updated_account.available_amount() > max_balance || updated_account.held_amount() > max_balance || updated_account.total_amount() > max_balance => {
+            Err(OperationValidationError::BalanceOverflow)
+        }
+        (Some(updated_account), _) => Ok(updated_account),
+        (None, _) => Err(OperationValidationError::BalanceOverflow)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum OperationValidationError {
     AccountNotFound,
     AccountFrozen,
     NegativeAmount,
+    NotEnoughAvailableAmount,
     TransactionIdAlreadyExisting,
     TransactionNotFound,
     ReferencedTransactionIsNotDeposit,
     ReferencedTransactionIsNotDisputedDeposit,
+    TimestampOutOfOrder,
+    DisputeWindowExpired,
+    ChargebackDeadlineExpired,
+    BalanceOverflow,
 }
 
 impl std::fmt::Display for OperationValidationError {
@@ -71,10 +131,15 @@ impl std::fmt::Display for OperationValidationError {
             OperationValidationError::AccountNotFound => "AccountNotFound",
             OperationValidationError::AccountFrozen => "AccountFrozen",
             OperationValidationError::NegativeAmount => "NegativeAmount",
+            OperationValidationError::NotEnoughAvailableAmount => "NotEnoughAvailableAmount",
             OperationValidationError::TransactionIdAlreadyExisting => "TransactionIdAlreadyExisting",
             OperationValidationError::TransactionNotFound => "TransactionNotFound",
             OperationValidationError::ReferencedTransactionIsNotDeposit => "ReferencedTransactionIsNotDepositWithoutDispute",
-            OperationValidationError::ReferencedTransactionIsNotDisputedDeposit => "ReferencedTransactionIsNotDisputedDeposit"
+            OperationValidationError::ReferencedTransactionIsNotDisputedDeposit => "ReferencedTransactionIsNotDisputedDeposit",
+            OperationValidationError::TimestampOutOfOrder => "TimestampOutOfOrder",
+            OperationValidationError::DisputeWindowExpired => "DisputeWindowExpired",
+            OperationValidationError::ChargebackDeadlineExpired => "ChargebackDeadlineExpired",
+            OperationValidationError::BalanceOverflow => "BalanceOverflow"
         };
         write!(f, "{error}")
     }
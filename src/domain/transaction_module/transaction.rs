@@ -1,5 +1,6 @@
 use crate::domain::account_module::account::AccountId;
-use crate::domain::Amount;
+use crate::domain::{Amount, Timestamp};
+use crate::domain::payments_engine::operation_request::OperationReference;
 
 pub type TransactionId = u32;
 
@@ -15,58 +16,96 @@ pub enum Transaction {
 pub struct DepositTransaction {
     id: TransactionId,
     to_account_id: AccountId,
-    amount: Amount
+    amount: Amount,
+    operation: OperationReference
 }
 
 impl DepositTransaction {
-    pub(in crate::domain) fn new(id: TransactionId, to_account_id: AccountId, amount: Amount) -> Self {
+    pub(in crate::domain) fn new(id: TransactionId, to_account_id: AccountId, amount: Amount, operation: OperationReference) -> Self {
         Self {
             id,
             to_account_id,
             amount,
+            operation,
         }
     }
 
+    pub fn id(&self) -> TransactionId {
+        self.id
+    }
+
     pub fn amount(&self) -> Amount {
         self.amount
     }
 
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.operation.timestamp
+    }
+
+    pub fn operation(&self) -> OperationReference {
+        self.operation
+    }
+
     pub fn to_account_id(&self) -> AccountId {
         self.to_account_id
     }
 
-    pub fn open_dispute(&self) -> DisputedDepositTransaction {
+    pub fn open_dispute(&self, dispute_operation: OperationReference) -> DisputedDepositTransaction {
         DisputedDepositTransaction {
             id: self.id,
             to_account_id: self.to_account_id,
             amount: self.amount,
+            operation: self.operation,
+            dispute_operation,
         }
     }
 }
 
 
 
+/*
+  Besides the operation that created the deposit, it keeps the one that opened the dispute, which the chargeback deadline starts from.
+*/
 #[derive(Clone)]
 pub struct DisputedDepositTransaction {
     id: TransactionId,
     to_account_id: AccountId,
-    amount: Amount
+    amount: Amount,
+    operation: OperationReference,
+    dispute_operation: OperationReference
 }
 
 impl DisputedDepositTransaction {
+    pub fn id(&self) -> TransactionId {
+        self.id
+    }
+
     pub fn amount(&self) -> Amount {
         self.amount
     }
 
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.operation.timestamp
+    }
+
+    pub fn operation(&self) -> OperationReference {
+        self.operation
+    }
+
     pub fn to_account_id(&self) -> AccountId {
         self.to_account_id
     }
 
+    pub fn dispute_operation(&self) -> OperationReference {
+        self.dispute_operation
+    }
+
     pub fn resolve_dispute(&self) -> DepositTransaction {
         DepositTransaction {
             id: self.id,
             to_account_id: self.to_account_id,
             amount: self.amount,
+            operation: self.operation,
         }
     }
 
@@ -75,41 +114,53 @@ impl DisputedDepositTransaction {
             id: self.id,
             to_account_id: self.to_account_id,
             amount: self.amount,
+            operation: self.operation,
         }
     }
 }
 
-
-#[allow(dead_code)]
 #[derive(Clone)]
 pub struct ChargedBackDepositTransaction {
     id: TransactionId,
     to_account_id: AccountId,
-    amount: Amount
+    amount: Amount,
+    operation: OperationReference
 }
 
-
-#[allow(dead_code)]
 #[derive(Clone)]
 pub struct WithdrawalTransaction {
     id: TransactionId,
     from_account_id: AccountId,
-    amount: Amount
+    amount: Amount,
+    operation: OperationReference
 }
 
 impl WithdrawalTransaction {
 
-    pub fn new(id: TransactionId, from_account_id: AccountId, amount: Amount) -> Self {
+    pub(in crate::domain) fn new(id: TransactionId, from_account_id: AccountId, amount: Amount, operation: OperationReference) -> Self {
         Self {
             id,
             from_account_id,
             amount,
+            operation,
         }
     }
 
+    pub fn id(&self) -> TransactionId {
+        self.id
+    }
+
     pub fn amount(&self) -> Amount {
         self.amount
     }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.operation.timestamp
+    }
+
+    pub fn operation(&self) -> OperationReference {
+        self.operation
+    }
 }
 
 impl Transaction {
@@ -121,4 +172,34 @@ impl Transaction {
             Transaction::ChargedBackDeposit(charged_back_transaction) => charged_back_transaction.id,
         }
     }
+
+    pub fn account_id(&self) -> AccountId {
+        match self {
+            Transaction::Deposit(deposit_transaction) => deposit_transaction.to_account_id,
+            Transaction::Withdrawal(withdrawal_transaction) => withdrawal_transaction.from_account_id,
+            Transaction::DisputedDeposit(disputed_deposit_transaction) => disputed_deposit_transaction.to_account_id,
+            Transaction::ChargedBackDeposit(charged_back_transaction) => charged_back_transaction.to_account_id,
+        }
+    }
+
+    pub fn amount(&self) -> Amount {
+        match self {
+            Transaction::Deposit(deposit_transaction) => deposit_transaction.amount,
+            Transaction::Withdrawal(withdrawal_transaction) => withdrawal_transaction.amount,
+            Transaction::DisputedDeposit(disputed_deposit_transaction) => disputed_deposit_transaction.amount,
+            Transaction::ChargedBackDeposit(charged_back_transaction) => charged_back_transaction.amount,
+        }
+    }
+
+    /*
+      The time of the operation that created the transaction: disputes, resolves and chargebacks don't change it.
+    */
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        match self {
+            Transaction::Deposit(deposit_transaction) => deposit_transaction.operation.timestamp,
+            Transaction::Withdrawal(withdrawal_transaction) => withdrawal_transaction.operation.timestamp,
+            Transaction::DisputedDeposit(disputed_deposit_transaction) => disputed_deposit_transaction.operation.timestamp,
+            Transaction::ChargedBackDeposit(charged_back_transaction) => charged_back_transaction.operation.timestamp,
+        }
+    }
 }
\ No newline at end of file
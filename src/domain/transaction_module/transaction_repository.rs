@@ -1,13 +1,20 @@
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use crate::domain::transaction_module::transaction::{Transaction, TransactionId};
 
 pub struct TransactionRepository {
     transactions: HashMap<TransactionId, Transaction>
 }
 
+/*
+  The place of a transaction id in the repository, taken or not, found with a single lookup:
+  it's read to validate an operation, then the transaction after the operation is stored in it.
+*/
+pub(in crate::domain) struct TransactionSlot<'a>(Entry<'a, TransactionId, Transaction>);
+
 impl TransactionRepository {
 
-    pub fn new() -> Self {
+    pub(in crate::domain) fn new() -> Self {
         Self {
             transactions: HashMap::new()
         }
@@ -16,7 +23,23 @@ impl TransactionRepository {
         self.transactions.get(&transaction_id)
     }
 
-    pub fn store(&mut self, transaction: Transaction) {
-        self.transactions.insert(transaction.id(), transaction);
+    pub(in crate::domain) fn slot(&mut self, transaction_id: TransactionId) -> TransactionSlot<'_> {
+        TransactionSlot(self.transactions.entry(transaction_id))
+    }
+}
+
+impl TransactionSlot<'_> {
+    pub(in crate::domain) fn transaction(&self) -> Option<&Transaction> {
+        match &self.0 {
+            Entry::Occupied(occupied_entry) => Some(occupied_entry.get()),
+            Entry::Vacant(_) => None
+        }
+    }
+
+    pub(in crate::domain) fn store(self, transaction: Transaction) {
+        match self.0 {
+            Entry::Occupied(mut occupied_entry) => { occupied_entry.insert(transaction); }
+            Entry::Vacant(vacant_entry) => { vacant_entry.insert(transaction); }
+        }
     }
 }
\ No newline at end of file
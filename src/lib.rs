@@ -0,0 +1,39 @@
+//! A simple payments engine: it processes deposits, withdrawals, disputes, resolves and chargebacks
+//! and keeps track of the resulting client accounts.
+//!
+//! The stable API is re-exported here: build a [`PaymentsEngine`] (optionally with an [`EnginePolicy`]), submit [`OperationRequest`]s to it
+//! and query the resulting [`Account`]s and [`Transaction`]s.
+//! The modules behind it (the input readers, the processing session, the output printer...) are public only for the binary,
+//! the benchmarks and the fuzz targets: they are hidden from the documentation and can change at any time.
+//!
+//! ```
+//! use simple_payments_engine::{Amount, DepositOperationRequest, OperationRequest, PaymentsEngine, WithdrawalOperationRequest, OperationValidationError};
+//!
+//! let mut payments_engine = PaymentsEngine::new();
+//!
+//! let deposit = OperationRequest::Deposit(DepositOperationRequest { account_id: 1, transaction_id: 1, amount: Amount::new(15, 1), timestamp: None });
+//! assert!(payments_engine.process(deposit).is_ok());
+//!
+//! let withdrawal = OperationRequest::Withdrawal(WithdrawalOperationRequest { account_id: 1, transaction_id: 2, amount: Amount::new(2, 0), timestamp: None });
+//! assert!(matches!(payments_engine.process(withdrawal).unwrap_err().as_slice(), [OperationValidationError::NotEnoughAvailableAmount]));
+//!
+//! assert_eq!(payments_engine.account(1).unwrap().available_amount(), Amount::new(15, 1));
+//! assert_eq!(payments_engine.transaction(1).unwrap().account_id(), 1);
+//! ```
+
+#[doc(hidden)]
+pub mod domain;
+#[doc(hidden)]
+pub mod use_case;
+#[doc(hidden)]
+pub mod output_printer;
+
+pub use domain::{Amount, Timestamp};
+pub use domain::account_module::account::{Account, AccountId, ActiveAccount, FrozenAccount};
+pub use domain::account_module::account_event::{AccountEvent, AccountEventKind};
+pub use domain::transaction_module::transaction::{ChargedBackDepositTransaction, DepositTransaction, DisputedDepositTransaction, Transaction, TransactionId, WithdrawalTransaction};
+pub use domain::payments_engine::{OperationValidationError, PaymentsEngine};
+pub use domain::payments_engine::engine_metrics::{EngineMetrics, LatencyHistogram};
+pub use domain::payments_engine::invariant_check::{InvariantViolation, InvariantViolationKind};
+pub use domain::payments_engine::engine_policy::{BackwardsTimestampsPolicy, EnginePolicy, TimeLimit};
+pub use domain::payments_engine::operation_request::{ChargebackOperationRequest, DepositOperationRequest, DisputeOperationRequest, OperationReference, OperationRequest, ResolveOperationRequest, WithdrawalOperationRequest};
@@ -1,30 +1,174 @@
-mod output_printer;
-mod domain;
-mod use_case;
+mod cli;
 
-use std::env;
 use std::error::Error;
-use crate::domain::payments_engine::PaymentsEngine;
-use crate::output_printer::print_outcome_to_stdout;
-use crate::use_case::process_from_csv_use_case::process_from_csv;
+use std::io::IsTerminal;
+use clap::Parser;
+use simple_payments_engine::{Account, Amount, EnginePolicy, PaymentsEngine};
+use simple_payments_engine::output_printer::{print_account_history, print_outcome, OutputSettings};
+use simple_payments_engine::use_case::account_statement_use_case::{print_statement, AccountStatement, StatementRange};
+use simple_payments_engine::use_case::operation_input::{process_input_sources, InputSource};
+use simple_payments_engine::use_case::processing_session::{ProcessingSession, ProcessingStatistics, RejectionReport};
+use simple_payments_engine::use_case::processing_session::prometheus_metrics::write_prometheus_metrics_file;
+use simple_payments_engine::use_case::reconcile_use_case::{print_reconciliation_report, read_expected_balances, reconcile};
+use simple_payments_engine::use_case::serve_http_use_case::serve_http;
+use simple_payments_engine::use_case::serve_tcp_use_case::serve_tcp;
+use tokio::net::TcpListener;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use crate::cli::{Cli, Command, LogFormat, SharedOptions};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
-    let arguments: Vec<String> = env::args().collect();
+    let cli = Cli::parse();
+    let shared_options = cli.shared_options;
 
-    if arguments.len() != 2 {
-        eprintln!("No arguments passed!\nUsage: {} <input_csv_file>", &arguments[0]);
-        std::process::exit(1);
-    }
+    init_logger(&shared_options);
 
-    env_logger::init();
+    let output_settings = OutputSettings {
+        format: shared_options.output_format,
+        sort_key: shared_options.sort_by,
+        output_path: shared_options.output.clone()
+    };
 
-    let payments_engine = PaymentsEngine::new();
+    let command = match cli.command {
+        Some(command) => command,
+        None if !cli.inputs.is_empty() => Command::Process { inputs: cli.inputs },
+        None => {
+            eprintln!("No arguments passed! Run with --help to see the usage");
+            std::process::exit(1);
+        }
+    };
 
-    let payments_engine_after_processing = process_from_csv(arguments[1].to_owned(), payments_engine).await.await??;
+    let payments_engine = PaymentsEngine::with_policy(load_engine_policy(&shared_options)?);
+    let payments_engine = if shared_options.check_invariants { payments_engine.with_invariant_checks() } else { payments_engine };
+    let payments_engine = if shared_options.metrics.is_some() { payments_engine.with_metrics() } else { payments_engine };
+    let processing_session = ProcessingSession::new(payments_engine);
+    let processing_session = match &shared_options.rejections {
+        Some(rejections_path) => processing_session.with_rejection_report(RejectionReport::create(rejections_path)?),
+        None => processing_session
+    };
 
-    print_outcome_to_stdout(payments_engine_after_processing.accounts());
+    match command {
+        Command::Process { inputs } => {
+            let (payments_engine, _) = process_inputs(&inputs, &shared_options, processing_session).await?;
+            print_result(&payments_engine, &shared_options, &output_settings);
+        }
+        Command::Validate { inputs } => {
+            let (_, statistics) = process_inputs(&inputs, &shared_options, processing_session).await?;
+            print!("{statistics}");
+            if statistics.rows_rejected > 0 {
+                std::process::exit(1);
+            }
+        }
+        Command::Replay { until_row, inputs } => {
+            let (payments_engine, _) = process_inputs(&inputs, &shared_options, processing_session.with_row_limit(until_row)).await?;
+            print_result(&payments_engine, &shared_options, &output_settings);
+        }
+        Command::Stats { inputs } => {
+            let (payments_engine, statistics) = process_inputs(&inputs, &shared_options, processing_session).await?;
+            print_statistics(&payments_engine, &statistics);
+        }
+        Command::Statement { client, from_operation, to_operation, from_time, to_time, statement_format, inputs } => {
+            let (payments_engine, _) = process_inputs(&inputs, &shared_options, processing_session).await?;
+            let statement_range = StatementRange { first_operation: from_operation, last_operation: to_operation, from_time, to_time };
+            print_statement(&AccountStatement::generate(&payments_engine, client, statement_range), statement_format, &output_settings);
+        }
+        Command::Reconcile { expected, inputs } => {
+            let expected_account_rows = read_expected_balances(std::fs::File::open(expected)?)?;
+            let (payments_engine, _) = process_inputs(&inputs, &shared_options, processing_session).await?;
+            let balance_differences = reconcile(&payments_engine, &expected_account_rows);
+            print_reconciliation_report(&balance_differences, &output_settings);
+            if !balance_differences.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Command::Serve { address } => {
+            let listener = TcpListener::bind(&address).await?;
+            let (payments_engine, statistics) = serve_tcp(listener, processing_session, output_settings.clone(), shutdown_signal()).await?.finish()?;
+            write_metrics(&payments_engine, &statistics, &shared_options)?;
+            print_result(&payments_engine, &shared_options, &output_settings);
+        }
+        Command::ServeHttp { address } => {
+            let listener = TcpListener::bind(&address).await?;
+            let (payments_engine, statistics) = serve_http(listener, processing_session, shutdown_signal()).await?.finish()?;
+            write_metrics(&payments_engine, &statistics, &shared_options)?;
+            print_result(&payments_engine, &shared_options, &output_settings);
+        }
+    }
 
     Ok(())
 }
 
+async fn shutdown_signal() {
+    if let Err(error) = tokio::signal::ctrl_c().await {
+        tracing::error!(%error, "Error while waiting for Ctrl+C, shutting down");
+    }
+}
+
+async fn process_inputs(inputs: &[String], shared_options: &SharedOptions, processing_session: ProcessingSession) -> Result<(PaymentsEngine, ProcessingStatistics), Box<dyn Error + Send + Sync>> {
+    let input_sources = inputs.iter().map(|input| InputSource::from_argument(input)).collect();
+    let processing_session = process_input_sources(input_sources, shared_options.input_format, processing_session).await?;
+    let (payments_engine, statistics) = processing_session.finish()?;
+    write_metrics(&payments_engine, &statistics, shared_options)?;
+    Ok((payments_engine, statistics))
+}
+
+fn write_metrics(payments_engine: &PaymentsEngine, statistics: &ProcessingStatistics, shared_options: &SharedOptions) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match &shared_options.metrics {
+        Some(metrics_path) => write_prometheus_metrics_file(metrics_path, statistics, payments_engine.metrics()),
+        None => Ok(())
+    }
+}
+
+fn print_result(payments_engine: &PaymentsEngine, shared_options: &SharedOptions, output_settings: &OutputSettings) {
+    match shared_options.account_history {
+        Some(account_id) => print_account_history(payments_engine.account_history(account_id), output_settings),
+        None => print_outcome(payments_engine.accounts(), output_settings)
+    }
+}
+
+fn print_statistics(payments_engine: &PaymentsEngine, statistics: &ProcessingStatistics) {
+    let accounts: Vec<&Account> = payments_engine.accounts().collect();
+
+    println!("{statistics}");
+    println!("accounts: {}", accounts.len());
+    println!("locked accounts: {}", accounts.iter().filter(|account| matches!(account, Account::Frozen(_))).count());
+    println!("total available: {}", checked_sum(accounts.iter().map(|account| account.available_amount())));
+    println!("total held: {}", checked_sum(accounts.iter().map(|account| account.held_amount())));
+}
+
+// Every account is within the amount limits, but their sum may not be
+fn checked_sum(mut amounts: impl Iterator<Item = Amount>) -> String {
+    amounts
+        .try_fold(Amount::ZERO, |total, amount| total.checked_add(amount))
+        .map_or_else(|| "overflow".to_string(), |total| total.to_string())
+}
+
+fn load_engine_policy(shared_options: &SharedOptions) -> Result<EnginePolicy, Box<dyn Error + Send + Sync>> {
+    match &shared_options.config {
+        Some(config_path) => Ok(toml::from_str(&std::fs::read_to_string(config_path)?)?),
+        None => Ok(EnginePolicy::default())
+    }
+}
+
+/*
+  Every log goes to stderr, with the fields of the spans it's in. A span is also logged when it closes, with its own fields,
+  so that at the debug level every operation shows up with its outcome even when it's accepted.
+*/
+fn init_logger(shared_options: &SharedOptions) {
+    let env_filter = match shared_options.log_level {
+        Some(log_level) => EnvFilter::default().add_directive(log_level.into()),
+        None => EnvFilter::builder().with_default_directive(LevelFilter::ERROR.into()).from_env_lossy()
+    };
+    let subscriber_builder = tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_span_events(FmtSpan::CLOSE);
+
+    match shared_options.log_format {
+        LogFormat::Text => subscriber_builder.init(),
+        LogFormat::Json => subscriber_builder.json().init()
+    }
+}
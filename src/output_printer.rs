@@ -1,17 +1,246 @@
-use crate::domain::account_module::account::Account;
+use std::fs::File;
+use std::io::{stdout, BufWriter, Write};
+use serde::Serialize;
+use crate::domain::account_module::account::{Account, AccountId};
+use crate::domain::account_module::account_event::{AccountEvent, AccountEventKind};
+use crate::domain::transaction_module::transaction::TransactionId;
+use crate::domain::{Amount, Timestamp};
 
-pub fn print_outcome_to_stdout<'a>(accounts_iterator: impl Iterator<Item = &'a Account>) {
-    println!("client,available,held,total,locked");
-    for account in accounts_iterator {
-        println!(
+/*
+  Every amount is printed with the same scale, whatever the scale of the input was (e.g. 2 and 1.5 are printed as 2.0000 and 1.5000).
+*/
+const OUTPUT_AMOUNT_SCALE: u32 = 4;
+
+#[derive(Clone, Copy)]
+pub enum OutputFormat {
+    Csv,
+    Json,
+    Jsonl
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(OutputFormat::Csv),
+            "json" => Some(OutputFormat::Json),
+            "jsonl" => Some(OutputFormat::Jsonl),
+            _ => None
+        }
+    }
+}
+
+/*
+  Accounts are always printed in a stable order, ascending on the chosen key. Ties are broken by client id.
+*/
+#[derive(Clone, Copy)]
+pub enum AccountSortKey {
+    Client,
+    Available,
+    Held,
+    Total
+}
+
+impl AccountSortKey {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "client" => Some(AccountSortKey::Client),
+            "available" => Some(AccountSortKey::Available),
+            "held" => Some(AccountSortKey::Held),
+            "total" => Some(AccountSortKey::Total),
+            _ => None
+        }
+    }
+}
+
+/*
+  Without an output path, the outcome is printed to stdout.
+*/
+#[derive(Clone)]
+pub struct OutputSettings {
+    pub format: OutputFormat,
+    pub sort_key: AccountSortKey,
+    pub output_path: Option<String>
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        OutputSettings { format: OutputFormat::Csv, sort_key: AccountSortKey::Client, output_path: None }
+    }
+}
+
+/*
+  The account as it's exposed outside the engine. Amounts are serialized as strings, to preserve the Decimal precision.
+*/
+#[derive(Serialize)]
+pub struct AccountOutputRow {
+    pub client: AccountId,
+    pub available: Amount,
+    pub held: Amount,
+    pub total: Amount,
+    pub locked: bool
+}
+
+impl AccountOutputRow {
+    pub fn from_account(account: &Account) -> Self {
+        AccountOutputRow {
+            client: account.id(),
+            available: with_output_scale(account.available_amount()),
+            held: with_output_scale(account.held_amount()),
+            total: with_output_scale(account.total_amount()),
+            locked: is_account_locked(account)
+        }
+    }
+}
+
+/*
+  An entry of the audit trail of an account. Balances are the ones right after the event.
+*/
+#[derive(Serialize)]
+pub struct AccountEventOutputRow {
+    pub operation: u64,
+    pub event: AccountEventKind,
+    pub client: AccountId,
+    pub tx: TransactionId,
+    pub amount: Amount,
+    pub available: Amount,
+    pub held: Amount,
+    pub total: Amount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<Timestamp>
+}
+
+impl AccountEventOutputRow {
+    pub fn from_account_event(account_event: &AccountEvent) -> Self {
+        AccountEventOutputRow {
+            operation: account_event.operation_index(),
+            event: account_event.kind(),
+            client: account_event.account_id(),
+            tx: account_event.transaction_id(),
+            amount: with_output_scale(account_event.amount()),
+            available: with_output_scale(account_event.available_amount()),
+            held: with_output_scale(account_event.held_amount()),
+            total: with_output_scale(account_event.total_amount()),
+            timestamp: account_event.timestamp()
+        }
+    }
+}
+
+pub fn print_outcome<'a>(accounts_iterator: impl Iterator<Item = &'a Account>, output_settings: &OutputSettings) {
+    print_to_output(output_settings, |output| write_outcome(output, accounts_iterator, output_settings));
+}
+
+pub fn print_account_history(account_events: &[AccountEvent], output_settings: &OutputSettings) {
+    print_to_output(output_settings, |output| write_account_history(output, account_events, output_settings));
+}
+
+pub(crate) fn print_to_output(output_settings: &OutputSettings, write: impl FnOnce(&mut dyn Write) -> std::io::Result<()>) {
+    let print_result = match &output_settings.output_path {
+        Some(output_path) => File::create(output_path)
+            .and_then(|file| write_and_flush(&mut BufWriter::new(file), write)),
+        None => write_and_flush(&mut BufWriter::new(stdout().lock()), write)
+    };
+
+    if let Err(error) = print_result {
+        tracing::error!(%error, "Error while printing the outcome");
+    }
+}
+
+fn write_and_flush(output: &mut dyn Write, write: impl FnOnce(&mut dyn Write) -> std::io::Result<()>) -> std::io::Result<()> {
+    write(output)?;
+    output.flush()
+}
+
+pub fn write_outcome<'a>(output: &mut (impl Write + ?Sized), accounts_iterator: impl Iterator<Item = &'a Account>, output_settings: &OutputSettings) -> std::io::Result<()> {
+    let mut output_rows: Vec<AccountOutputRow> = accounts_iterator.map(AccountOutputRow::from_account).collect();
+    sort_output_rows(&mut output_rows, output_settings.sort_key);
+
+    match output_settings.format {
+        OutputFormat::Csv => write_csv(output, &output_rows),
+        OutputFormat::Json => write_json(output, &output_rows),
+        OutputFormat::Jsonl => write_jsonl(output, &output_rows)
+    }
+}
+
+/*
+  The history is always printed in the order the events happened, so the sort key doesn't apply.
+*/
+pub fn write_account_history(output: &mut (impl Write + ?Sized), account_events: &[AccountEvent], output_settings: &OutputSettings) -> std::io::Result<()> {
+    let output_rows: Vec<AccountEventOutputRow> = account_events.iter().map(AccountEventOutputRow::from_account_event).collect();
+
+    match output_settings.format {
+        OutputFormat::Csv => write_account_history_csv(output, &output_rows),
+        OutputFormat::Json => write_json(output, &output_rows),
+        OutputFormat::Jsonl => write_jsonl(output, &output_rows)
+    }
+}
+
+fn sort_output_rows(output_rows: &mut [AccountOutputRow], sort_key: AccountSortKey) {
+    match sort_key {
+        AccountSortKey::Client => output_rows.sort_by_key(|output_row| output_row.client),
+        AccountSortKey::Available => output_rows.sort_by_key(|output_row| (output_row.available, output_row.client)),
+        AccountSortKey::Held => output_rows.sort_by_key(|output_row| (output_row.held, output_row.client)),
+        AccountSortKey::Total => output_rows.sort_by_key(|output_row| (output_row.total, output_row.client)),
+    }
+}
+
+fn write_csv(output: &mut (impl Write + ?Sized), output_rows: &[AccountOutputRow]) -> std::io::Result<()> {
+    writeln!(output, "client,available,held,total,locked")?;
+    for output_row in output_rows {
+        writeln!(
+            output,
             "{},{},{},{},{}",
-            account.id(),
-            account.available_amount(),
-            account.held_amount(),
-            account.total_amount(),
-            is_account_locked(account)
-        );
+            output_row.client,
+            output_row.available,
+            output_row.held,
+            output_row.total,
+            output_row.locked
+        )?;
+    }
+    Ok(())
+}
+
+fn write_account_history_csv(output: &mut (impl Write + ?Sized), output_rows: &[AccountEventOutputRow]) -> std::io::Result<()> {
+    writeln!(output, "operation,event,client,tx,amount,available,held,total,timestamp")?;
+    for output_row in output_rows {
+        writeln!(
+            output,
+            "{},{},{},{},{},{},{},{},{}",
+            output_row.operation,
+            output_row.event,
+            output_row.client,
+            output_row.tx,
+            output_row.amount,
+            output_row.available,
+            output_row.held,
+            output_row.total,
+            output_row.timestamp.map(|timestamp| timestamp.to_rfc3339()).unwrap_or_default()
+        )?;
+    }
+    Ok(())
+}
+
+fn write_json(output: &mut (impl Write + ?Sized), output_rows: &[impl Serialize]) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *output, output_rows)?;
+    writeln!(output)
+}
+
+fn write_jsonl(output: &mut (impl Write + ?Sized), output_rows: &[impl Serialize]) -> std::io::Result<()> {
+    for output_row in output_rows {
+        serde_json::to_writer(&mut *output, output_row)?;
+        writeln!(output)?;
     }
+    Ok(())
+}
+
+/*
+  A Decimal holds up to 79228162514264337593543950335 at any scale, so amounts past 7922816251426433759354395.0335 keep only
+  the decimals that fit: rescale never drops integer digits. Fixed point amounts always have four decimals.
+  A max_balance in the engine policy keeps every balance below that.
+*/
+pub(crate) fn with_output_scale(amount: Amount) -> Amount {
+    let mut scaled_amount = amount;
+    scaled_amount.rescale(OUTPUT_AMOUNT_SCALE);
+    scaled_amount
 }
 
 fn is_account_locked(account: &Account) -> bool {
@@ -19,4 +248,7 @@ fn is_account_locked(account: &Account) -> bool {
         Account::Active(_) => false,
         Account::Frozen(_) => true
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod output_printer_test;
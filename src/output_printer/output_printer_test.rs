@@ -0,0 +1,89 @@
+use crate::domain::Amount;
+use crate::domain::payments_engine::operation_request::{ChargebackOperationRequest, DepositOperationRequest, DisputeOperationRequest, OperationRequest, WithdrawalOperationRequest};
+use crate::domain::payments_engine::PaymentsEngine;
+use crate::output_printer::{write_account_history, write_outcome, AccountSortKey, OutputFormat, OutputSettings};
+
+fn payments_engine_with_deposits(deposits: &[(u16, u32, Amount)]) -> PaymentsEngine {
+    let mut payments_engine = PaymentsEngine::new();
+    for &(account_id, transaction_id, amount) in deposits {
+        payments_engine.process(OperationRequest::Deposit(DepositOperationRequest { account_id, transaction_id, amount, timestamp: None })).unwrap();
+    }
+    payments_engine
+}
+
+fn render(payments_engine: &PaymentsEngine, output_settings: &OutputSettings) -> String {
+    let mut output = vec![];
+    write_outcome(&mut output, payments_engine.accounts(), output_settings).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_csv_output_is_sorted_by_client_with_fixed_scale() {
+    let payments_engine = payments_engine_with_deposits(&[
+        (3, 1, Amount::new(2, 0)),
+        (1, 2, Amount::new(15, 1)),
+        (2, 3, Amount::new(15000, 4)),
+    ]);
+
+    let output = render(&payments_engine, &OutputSettings::default());
+
+    assert_eq!(output, "client,available,held,total,locked\n\
+        1,1.5000,0.0000,1.5000,false\n\
+        2,1.5000,0.0000,1.5000,false\n\
+        3,2.0000,0.0000,2.0000,false\n");
+}
+
+#[test]
+fn test_jsonl_output_is_sorted_by_total_with_ties_broken_by_client() {
+    let payments_engine = payments_engine_with_deposits(&[
+        (3, 1, Amount::new(2, 0)),
+        (2, 2, Amount::new(15, 1)),
+        (1, 3, Amount::new(15000, 4)),
+    ]);
+
+    let output = render(&payments_engine, &OutputSettings { format: OutputFormat::Jsonl, sort_key: AccountSortKey::Total, output_path: None });
+
+    assert_eq!(output, "{\"client\":1,\"available\":\"1.5000\",\"held\":\"0.0000\",\"total\":\"1.5000\",\"locked\":false}\n\
+        {\"client\":2,\"available\":\"1.5000\",\"held\":\"0.0000\",\"total\":\"1.5000\",\"locked\":false}\n\
+        {\"client\":3,\"available\":\"2.0000\",\"held\":\"0.0000\",\"total\":\"2.0000\",\"locked\":false}\n");
+}
+
+#[test]
+fn test_account_history_csv_output_lists_the_events_of_the_account_in_order() {
+    let mut payments_engine = payments_engine_with_deposits(&[
+        (1, 1, Amount::new(10, 0)),
+        (2, 2, Amount::new(7, 0)),
+        (1, 3, Amount::new(5, 0)),
+    ]);
+    payments_engine.process(OperationRequest::Withdrawal(WithdrawalOperationRequest { account_id: 1, transaction_id: 4, amount: Amount::new(25, 1), timestamp: None })).unwrap();
+    payments_engine.process(OperationRequest::Withdrawal(WithdrawalOperationRequest { account_id: 1, transaction_id: 5, amount: Amount::new(100, 0), timestamp: None })).unwrap_err();
+    payments_engine.process(OperationRequest::Dispute(DisputeOperationRequest { transaction_id: 3, timestamp: None })).unwrap();
+    payments_engine.process(OperationRequest::Chargeback(ChargebackOperationRequest { transaction_id: 3, timestamp: None })).unwrap();
+
+    let mut output = vec![];
+    write_account_history(&mut output, payments_engine.account_history(1), &OutputSettings::default()).unwrap();
+
+    assert_eq!(String::from_utf8(output).unwrap(), "operation,event,client,tx,amount,available,held,total,timestamp\n\
+        1,deposit,1,1,10.0000,10.0000,0.0000,10.0000,\n\
+        3,deposit,1,3,5.0000,15.0000,0.0000,15.0000,\n\
+        4,withdrawal,1,4,2.5000,12.5000,0.0000,12.5000,\n\
+        6,dispute_opened,1,3,5.0000,7.5000,5.0000,12.5000,\n\
+        7,charged_back,1,3,5.0000,7.5000,0.0000,7.5000,\n\
+        7,frozen,1,3,0.0000,7.5000,0.0000,7.5000,\n");
+    assert_eq!(payments_engine.account_transactions(1).map(|transaction| transaction.id()).collect::<Vec<_>>(), vec![1, 3, 4]);
+}
+
+/*
+  A Decimal holds up to 79228162514264337593543950335 (96 bits) at any scale: four decimals fit up to 7922816251426433759354395.0335,
+  past that only the decimals that fit are printed.
+*/
+#[cfg(not(feature = "fixed_point_amount"))]
+#[test]
+fn test_amounts_too_big_for_four_decimals_are_printed_with_the_decimals_that_fit() {
+    use std::str::FromStr;
+    use crate::output_printer::with_output_scale;
+
+    assert_eq!(with_output_scale(Amount::from_str("7922816251426433759354395.0335").unwrap()).to_string(), "7922816251426433759354395.0335");
+    assert_eq!(with_output_scale(Amount::from_str("7922816251426433759354395.5").unwrap()).to_string(), "7922816251426433759354395.500");
+    assert_eq!(with_output_scale(Amount::from_str("79228162514264337593543950335").unwrap()).to_string(), "79228162514264337593543950335");
+}
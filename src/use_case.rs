@@ -1 +1,9 @@
-pub mod process_from_csv_use_case;
\ No newline at end of file
+pub mod operation_input;
+pub mod processing_session;
+pub mod process_from_csv_use_case;
+pub mod process_from_jsonl_use_case;
+pub mod serve_tcp_use_case;
+pub mod serve_http_use_case;
+pub mod account_statement_use_case;
+pub mod reconcile_use_case;
+pub mod generate_workload_use_case;
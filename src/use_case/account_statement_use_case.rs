@@ -0,0 +1,235 @@
+use std::io::Write;
+
+use crate::domain::account_module::account::{exact_add, exact_sub, AccountId};
+use crate::domain::account_module::account_event::{AccountEvent, AccountEventKind};
+use crate::domain::{Amount, Timestamp};
+use crate::domain::payments_engine::PaymentsEngine;
+use crate::domain::transaction_module::transaction::TransactionId;
+use crate::output_printer::{print_to_output, with_output_scale, OutputSettings};
+
+#[derive(Clone, Copy)]
+pub enum StatementFormat {
+    Csv,
+    Text
+}
+
+impl StatementFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(StatementFormat::Csv),
+            "text" => Some(StatementFormat::Text),
+            _ => None
+        }
+    }
+}
+
+/*
+  Bounds are inclusive. Operation bounds refer to the operation index the engine gives to every submitted operation
+  (the same "operation" column of the account history), time bounds to the operation timestamps.
+  A missing bound means "from the first" or "up to the last" operation. Operations without a timestamp are never excluded by time bounds.
+  A statement always covers a contiguous part of the history: when timestamps went backwards, it starts right after the last operation
+  before the range and stops right before the first operation after it, so operations in between are part of it whatever their timestamp.
+*/
+#[derive(Clone, Copy, Default)]
+pub struct StatementRange {
+    pub first_operation: Option<u64>,
+    pub last_operation: Option<u64>,
+    pub from_time: Option<Timestamp>,
+    pub to_time: Option<Timestamp>
+}
+
+impl StatementRange {
+    fn is_before(&self, account_event: &AccountEvent) -> bool {
+        self.first_operation.is_some_and(|first_operation| account_event.operation_index() < first_operation)
+            || self.from_time.zip(account_event.timestamp()).is_some_and(|(from_time, timestamp)| timestamp < from_time)
+    }
+
+    fn is_after(&self, account_event: &AccountEvent) -> bool {
+        self.last_operation.is_some_and(|last_operation| account_event.operation_index() > last_operation)
+            || self.to_time.zip(account_event.timestamp()).is_some_and(|(to_time, timestamp)| timestamp > to_time)
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct StatementBalance {
+    pub available: Amount,
+    pub held: Amount
+}
+
+impl StatementBalance {
+    fn after(account_event: &AccountEvent) -> Self {
+        StatementBalance { available: account_event.available_amount(), held: account_event.held_amount() }
+    }
+
+    /*
+      The account never keeps balances whose total doesn't fit, so it's None only for balances that didn't come from an account.
+    */
+    pub fn total(&self) -> Option<Amount> {
+        exact_add(self.available, self.held)
+    }
+}
+
+pub struct StatementLine {
+    pub operation: u64,
+    pub timestamp: Option<Timestamp>,
+    pub event: AccountEventKind,
+    pub tx: TransactionId,
+    pub amount: Amount,
+    pub available_change: Option<Amount>,
+    pub held_change: Option<Amount>,
+    pub balance: StatementBalance
+}
+
+/*
+  Every operation applied to a client account in a range, with its effect on the available and held amounts
+  and the balances after it. The opening balance is the one right before the range, the closing balance the one at its end.
+  Rejected operations never touched the account, so they are not part of the statement.
+*/
+pub struct AccountStatement {
+    pub client: AccountId,
+    pub range: StatementRange,
+    pub opening_balance: StatementBalance,
+    pub closing_balance: StatementBalance,
+    pub locked: bool,
+    pub lines: Vec<StatementLine>
+}
+
+impl AccountStatement {
+    pub fn generate(payments_engine: &PaymentsEngine, client: AccountId, range: StatementRange) -> Self {
+        let account_history = payments_engine.account_history(client);
+        let first_event_index = account_history.iter()
+            .rposition(|account_event| range.is_before(account_event))
+            .map_or(0, |last_event_before_index| last_event_before_index + 1);
+        let end_event_index = account_history[first_event_index..].iter()
+            .position(|account_event| range.is_after(account_event))
+            .map_or(account_history.len(), |first_event_after_position| first_event_index + first_event_after_position);
+
+        let opening_balance = first_event_index.checked_sub(1)
+            .map(|last_event_before_index| StatementBalance::after(&account_history[last_event_before_index]))
+            .unwrap_or_default();
+
+        let mut balance = opening_balance;
+        let mut locked = account_history[..first_event_index].iter().any(|account_event| account_event.kind() == AccountEventKind::Frozen);
+        let mut lines = vec![];
+
+        for account_event in &account_history[first_event_index..end_event_index] {
+            // The freeze is a consequence of the chargeback, not an operation on its own
+            if account_event.kind() == AccountEventKind::Frozen {
+                locked = true;
+                continue;
+            }
+
+            // The range is contiguous, so the balance so far is the one right before this event
+            let balance_after_event = StatementBalance::after(account_event);
+            lines.push(StatementLine {
+                operation: account_event.operation_index(),
+                timestamp: account_event.timestamp(),
+                event: account_event.kind(),
+                tx: account_event.transaction_id(),
+                amount: account_event.amount(),
+                available_change: exact_sub(balance_after_event.available, balance.available),
+                held_change: exact_sub(balance_after_event.held, balance.held),
+                balance: balance_after_event
+            });
+            balance = balance_after_event;
+        }
+
+        AccountStatement { client, range, opening_balance, closing_balance: balance, locked, lines }
+    }
+}
+
+pub fn print_statement(account_statement: &AccountStatement, statement_format: StatementFormat, output_settings: &OutputSettings) {
+    print_to_output(output_settings, |output| write_statement(output, account_statement, statement_format));
+}
+
+pub fn write_statement(output: &mut (impl Write + ?Sized), account_statement: &AccountStatement, statement_format: StatementFormat) -> std::io::Result<()> {
+    match statement_format {
+        StatementFormat::Csv => write_statement_csv(output, account_statement),
+        StatementFormat::Text => write_statement_text(output, account_statement)
+    }
+}
+
+/*
+  Opening and closing balances are rows of their own, so that the CSV can be imported as it is.
+*/
+fn write_statement_csv(output: &mut (impl Write + ?Sized), account_statement: &AccountStatement) -> std::io::Result<()> {
+    let opening_balance = &account_statement.opening_balance;
+    let closing_balance = &account_statement.closing_balance;
+
+    writeln!(output, "operation,event,client,tx,amount,available_change,held_change,available,held,total,timestamp")?;
+    writeln!(output, ",opening_balance,{},,,,,{},{},{},", account_statement.client, with_output_scale(opening_balance.available), with_output_scale(opening_balance.held), output_amount(opening_balance.total()))?;
+    for line in &account_statement.lines {
+        writeln!(
+            output,
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            line.operation,
+            line.event,
+            account_statement.client,
+            line.tx,
+            with_output_scale(line.amount),
+            output_amount(line.available_change),
+            output_amount(line.held_change),
+            with_output_scale(line.balance.available),
+            with_output_scale(line.balance.held),
+            output_amount(line.balance.total()),
+            line.timestamp.map(|timestamp| timestamp.to_rfc3339()).unwrap_or_default()
+        )?;
+    }
+    writeln!(output, ",closing_balance,{},,,,,{},{},{},", account_statement.client, with_output_scale(closing_balance.available), with_output_scale(closing_balance.held), output_amount(closing_balance.total()))
+}
+
+fn write_statement_text(output: &mut (impl Write + ?Sized), account_statement: &AccountStatement) -> std::io::Result<()> {
+    let range = account_statement.range;
+    let first_operation = range.first_operation.map_or("the first operation".to_string(), |first_operation| format!("operation {first_operation}"));
+    let last_operation = range.last_operation.map_or("the last operation".to_string(), |last_operation| format!("operation {last_operation}"));
+
+    writeln!(output, "Statement of client {}, from {first_operation} to {last_operation}", account_statement.client)?;
+    if range.from_time.is_some() || range.to_time.is_some() {
+        let from_time = range.from_time.map_or("the beginning".to_string(), |from_time| from_time.to_rfc3339());
+        let to_time = range.to_time.map_or("now".to_string(), |to_time| to_time.to_rfc3339());
+        writeln!(output, "Time range: from {from_time} to {to_time}")?;
+    }
+    writeln!(output)?;
+    write_text_balance(output, "Opening balance", &account_statement.opening_balance)?;
+    writeln!(output)?;
+    writeln!(output, "{:>10}  {:<18}{:>10}{:>18}{:>18}{:>18}{:>18}", "operation", "event", "tx", "available change", "held change", "available", "held")?;
+    for line in &account_statement.lines {
+        writeln!(
+            output,
+            "{:>10}  {:<18}{:>10}{:>18}{:>18}{:>18}{:>18}",
+            line.operation,
+            line.event.to_string(),
+            line.tx,
+            output_amount(line.available_change),
+            output_amount(line.held_change),
+            with_output_scale(line.balance.available).to_string(),
+            with_output_scale(line.balance.held).to_string()
+        )?;
+    }
+    writeln!(output)?;
+    write_text_balance(output, "Closing balance", &account_statement.closing_balance)?;
+    if account_statement.locked {
+        writeln!(output, "The account is locked")?;
+    }
+    Ok(())
+}
+
+fn write_text_balance(output: &mut (impl Write + ?Sized), title: &str, balance: &StatementBalance) -> std::io::Result<()> {
+    writeln!(
+        output,
+        "{title}: available {}, held {}, total {}",
+        with_output_scale(balance.available),
+        with_output_scale(balance.held),
+        output_amount(balance.total())
+    )
+}
+
+/*
+  An amount that doesn't fit is left empty rather than printed wrong.
+*/
+fn output_amount(maybe_amount: Option<Amount>) -> String {
+    maybe_amount.map(|amount| with_output_scale(amount).to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod account_statement_use_case_test;
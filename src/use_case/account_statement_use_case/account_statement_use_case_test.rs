@@ -0,0 +1,80 @@
+use crate::domain::{Amount, Timestamp};
+use crate::domain::payments_engine::operation_request::{ChargebackOperationRequest, DepositOperationRequest, DisputeOperationRequest, OperationRequest, WithdrawalOperationRequest};
+use crate::domain::payments_engine::PaymentsEngine;
+use crate::use_case::account_statement_use_case::{write_statement, AccountStatement, StatementBalance, StatementFormat, StatementRange};
+
+fn payments_engine_with_history() -> PaymentsEngine {
+    let mut payments_engine = PaymentsEngine::new();
+    let operation_requests = [
+        OperationRequest::Deposit(DepositOperationRequest { account_id: 1, transaction_id: 1, amount: Amount::new(10, 0), timestamp: None }),
+        OperationRequest::Deposit(DepositOperationRequest { account_id: 2, transaction_id: 2, amount: Amount::new(7, 0), timestamp: None }),
+        OperationRequest::Deposit(DepositOperationRequest { account_id: 1, transaction_id: 3, amount: Amount::new(5, 0), timestamp: None }),
+        OperationRequest::Withdrawal(WithdrawalOperationRequest { account_id: 1, transaction_id: 4, amount: Amount::new(25, 1), timestamp: None }),
+        OperationRequest::Dispute(DisputeOperationRequest { transaction_id: 3, timestamp: None }),
+        OperationRequest::Chargeback(ChargebackOperationRequest { transaction_id: 3, timestamp: None }),
+    ];
+    for operation_request in operation_requests {
+        payments_engine.process(operation_request).unwrap();
+    }
+    payments_engine
+}
+
+#[test]
+fn test_statement_over_a_range_starts_from_the_balance_before_it() {
+    let payments_engine = payments_engine_with_history();
+
+    let account_statement = AccountStatement::generate(&payments_engine, 1, StatementRange { first_operation: Some(4), last_operation: Some(5), ..StatementRange::default() });
+
+    assert_eq!(account_statement.opening_balance, StatementBalance { available: Amount::new(15, 0), held: Amount::ZERO });
+    assert_eq!(account_statement.closing_balance, StatementBalance { available: Amount::new(75, 1), held: Amount::new(5, 0) });
+    assert!(!account_statement.locked);
+
+    let mut output = vec![];
+    write_statement(&mut output, &account_statement, StatementFormat::Csv).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "operation,event,client,tx,amount,available_change,held_change,available,held,total,timestamp\n\
+        ,opening_balance,1,,,,,15.0000,0.0000,15.0000,\n\
+        4,withdrawal,1,4,2.5000,-2.5000,0.0000,12.5000,0.0000,12.5000,\n\
+        5,dispute_opened,1,3,5.0000,-5.0000,5.0000,7.5000,5.0000,12.5000,\n\
+        ,closing_balance,1,,,,,7.5000,5.0000,12.5000,\n");
+}
+
+#[test]
+fn test_statement_of_a_charged_back_account_is_locked() {
+    let payments_engine = payments_engine_with_history();
+
+    let account_statement = AccountStatement::generate(&payments_engine, 1, StatementRange::default());
+
+    assert_eq!(account_statement.opening_balance, StatementBalance::default());
+    assert_eq!(account_statement.lines.len(), 5);
+    assert_eq!(account_statement.closing_balance, StatementBalance { available: Amount::new(75, 1), held: Amount::ZERO });
+    assert!(account_statement.locked);
+}
+
+#[test]
+fn test_statement_covers_a_contiguous_part_of_a_history_with_backwards_and_missing_timestamps() {
+    let mut payments_engine = PaymentsEngine::new();
+    let timestamp = |time: &str| Some(format!("2024-03-01T{time}Z").parse::<Timestamp>().unwrap());
+    let operation_requests = [
+        OperationRequest::Deposit(DepositOperationRequest { account_id: 1, transaction_id: 1, amount: Amount::new(10, 0), timestamp: timestamp("10:00:00") }),
+        OperationRequest::Deposit(DepositOperationRequest { account_id: 1, transaction_id: 2, amount: Amount::new(1, 0), timestamp: None }),
+        OperationRequest::Deposit(DepositOperationRequest { account_id: 1, transaction_id: 3, amount: Amount::new(2, 0), timestamp: timestamp("09:00:00") }),
+        OperationRequest::Withdrawal(WithdrawalOperationRequest { account_id: 1, transaction_id: 4, amount: Amount::new(4, 0), timestamp: timestamp("11:00:00") }),
+        OperationRequest::Deposit(DepositOperationRequest { account_id: 1, transaction_id: 5, amount: Amount::new(3, 0), timestamp: timestamp("12:00:00") }),
+    ];
+    for operation_request in operation_requests {
+        payments_engine.process(operation_request).unwrap();
+    }
+
+    // The deposit of 09:00 went back in time: the statement starts after it, not with the deposits of 10:00 and without a timestamp
+    let account_statement = AccountStatement::generate(&payments_engine, 1, StatementRange { from_time: timestamp("09:30:00"), to_time: timestamp("11:30:00"), ..StatementRange::default() });
+    assert_eq!(account_statement.opening_balance, StatementBalance { available: Amount::new(13, 0), held: Amount::ZERO });
+    assert_eq!(account_statement.lines.iter().map(|line| line.tx).collect::<Vec<_>>(), vec![4]);
+    assert_eq!(account_statement.lines[0].available_change, Some(Amount::new(-4, 0)));
+    assert_eq!(account_statement.lines[0].held_change, Some(Amount::ZERO));
+    assert_eq!(account_statement.closing_balance, StatementBalance { available: Amount::new(9, 0), held: Amount::ZERO });
+
+    // The first operation is already after the range, so nothing later is part of it
+    let account_statement = AccountStatement::generate(&payments_engine, 1, StatementRange { to_time: timestamp("09:30:00"), ..StatementRange::default() });
+    assert!(account_statement.lines.is_empty());
+    assert_eq!(account_statement.closing_balance, StatementBalance::default());
+}
@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use crate::domain::account_module::account::AccountId;
+use crate::domain::transaction_module::transaction::TransactionId;
+
+/*
+  How many recent deposits can be disputed, and how many disputes can be open at the same time.
+  Both are bounded, so that generating hundreds of millions of rows takes constant memory.
+*/
+const DISPUTABLE_DEPOSITS: usize = 10_000;
+const OPEN_DISPUTES: usize = 10_000;
+
+// Amounts are generated in minor units (1/10000) and written with 4 decimals, like the engine prints them
+const MINOR_UNITS: i64 = 10_000;
+const MAX_DEPOSIT_MINOR_UNITS: u64 = 1_000 * MINOR_UNITS as u64;
+
+/*
+  The shape of a generated workload. Rates are fractions between 0 and 1:
+  withdrawal_rate and dispute_rate of all the rows, chargeback_rate of the disputes (the other ones are resolved).
+  The same seed always generates the same workload.
+*/
+#[derive(Clone)]
+pub struct WorkloadSettings {
+    pub rows: u64,
+    pub clients: AccountId,
+    pub withdrawal_rate: f64,
+    pub dispute_rate: f64,
+    pub chargeback_rate: f64,
+    pub seed: u64
+}
+
+impl Default for WorkloadSettings {
+    fn default() -> Self {
+        WorkloadSettings { rows: 1_000_000, clients: 1_000, withdrawal_rate: 0.3, dispute_rate: 0.01, chargeback_rate: 0.1, seed: 42 }
+    }
+}
+
+/*
+  A xorshift64* generator: not meant for anything but generating test data, and it keeps the workload reproducible without extra dependencies.
+*/
+struct WorkloadRandom {
+    state: u64
+}
+
+impl WorkloadRandom {
+    fn new(seed: u64) -> Self {
+        WorkloadRandom { state: seed.max(1) }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn chance(&mut self, rate: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < rate
+    }
+}
+
+/*
+  Writes a realistic CSV of operations: deposits and withdrawals spread across the clients, withdrawals mostly within the balance
+  (about 1 in 100 goes past it and gets rejected), disputes on recent deposits that are later resolved or charged back.
+  A chargeback freezes the client, so new operations go to the other clients (a few attempts, then a frozen one is fine: it's rejected).
+  Chargebacks eventually freeze every client: big workloads need more clients or a lower chargeback rate to stay realistic.
+*/
+pub fn write_workload(output: &mut impl Write, workload_settings: &WorkloadSettings) -> std::io::Result<()> {
+    let mut random = WorkloadRandom::new(workload_settings.seed);
+    let clients = workload_settings.clients.max(1);
+    let mut balances = vec![0i64; usize::from(clients) + 1];
+    let mut frozen_clients = vec![false; usize::from(clients) + 1];
+    let mut disputable_deposits: Vec<(TransactionId, AccountId)> = Vec::with_capacity(DISPUTABLE_DEPOSITS);
+    let mut open_disputes: VecDeque<(TransactionId, AccountId)> = VecDeque::with_capacity(OPEN_DISPUTES);
+    let mut next_transaction_id: TransactionId = 1;
+
+    writeln!(output, "type,client,tx,amount")?;
+    for _ in 0..workload_settings.rows {
+        if !open_disputes.is_empty() && (open_disputes.len() == OPEN_DISPUTES || random.chance(workload_settings.dispute_rate)) {
+            let (transaction_id, client) = open_disputes.pop_front().unwrap();
+            if random.chance(workload_settings.chargeback_rate) {
+                writeln!(output, "chargeback,{client},{transaction_id},")?;
+                frozen_clients[usize::from(client)] = true;
+            } else {
+                writeln!(output, "resolve,{client},{transaction_id},")?;
+            }
+            continue;
+        }
+
+        if !disputable_deposits.is_empty() && random.chance(workload_settings.dispute_rate) {
+            let (transaction_id, client) = disputable_deposits.swap_remove(random.below(disputable_deposits.len() as u64) as usize);
+            writeln!(output, "dispute,{client},{transaction_id},")?;
+            open_disputes.push_back((transaction_id, client));
+            continue;
+        }
+
+        let mut client = 1 + random.below(u64::from(clients)) as AccountId;
+        for _ in 0..8 {
+            if !frozen_clients[usize::from(client)] {
+                break;
+            }
+            client = 1 + random.below(u64::from(clients)) as AccountId;
+        }
+        let transaction_id = next_transaction_id;
+        next_transaction_id = next_transaction_id.checked_add(1).ok_or_else(|| std::io::Error::other("Too many rows: transaction ids are exhausted"))?;
+        let balance = &mut balances[usize::from(client)];
+
+        if *balance > 0 && random.chance(workload_settings.withdrawal_rate) {
+            let amount = if random.chance(0.01) { *balance + 1 } else { 1 + random.below(*balance as u64) as i64 };
+            writeln!(output, "withdrawal,{client},{transaction_id},{}", format_minor_units(amount))?;
+            if amount <= *balance {
+                *balance -= amount;
+            }
+        } else {
+            let amount = 1 + random.below(MAX_DEPOSIT_MINOR_UNITS) as i64;
+            writeln!(output, "deposit,{client},{transaction_id},{}", format_minor_units(amount))?;
+            *balance += amount;
+            if disputable_deposits.len() < DISPUTABLE_DEPOSITS {
+                disputable_deposits.push((transaction_id, client));
+            } else {
+                let replaced_index = random.below(DISPUTABLE_DEPOSITS as u64) as usize;
+                disputable_deposits[replaced_index] = (transaction_id, client);
+            }
+        }
+    }
+    output.flush()
+}
+
+fn format_minor_units(amount: i64) -> String {
+    format!("{}.{:04}", amount / MINOR_UNITS, amount % MINOR_UNITS)
+}
@@ -0,0 +1,129 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use futures::{Stream, StreamExt};
+use tracing::error;
+
+use crate::domain::account_module::account::AccountId;
+use crate::domain::payments_engine::operation_request::OperationRequest;
+use crate::domain::payments_engine::OperationValidationError;
+use crate::domain::transaction_module::transaction::TransactionId;
+use crate::use_case::process_from_csv_use_case::{process_from_csv, CsvOperationType};
+use crate::use_case::process_from_jsonl_use_case::process_from_jsonl;
+use crate::use_case::processing_session::ProcessingSession;
+
+/*
+  Where the operations come from: "-" on the command line means stdin, anything else is a file path.
+*/
+#[derive(Clone)]
+pub enum InputSource {
+    Stdin,
+    File(String)
+}
+
+impl InputSource {
+    pub fn from_argument(argument: &str) -> Self {
+        if argument == "-" {
+            InputSource::Stdin
+        } else {
+            InputSource::File(argument.to_owned())
+        }
+    }
+}
+
+impl Display for InputSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputSource::Stdin => write!(f, "stdin"),
+            InputSource::File(file_path) => write!(f, "{file_path}")
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum InputFormat {
+    Csv,
+    Jsonl
+}
+
+impl InputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(InputFormat::Csv),
+            "jsonl" => Some(InputFormat::Jsonl),
+            _ => None
+        }
+    }
+
+    /*
+      Files ending with .jsonl or .ndjson are read as JSON Lines, everything else (stdin included) as CSV.
+    */
+    pub fn from_input_source(input_source: &InputSource) -> Self {
+        match input_source {
+            InputSource::File(file_path) if file_path.ends_with(".jsonl") || file_path.ends_with(".ndjson") => InputFormat::Jsonl,
+            _ => InputFormat::Csv
+        }
+    }
+}
+
+/*
+  The reason why a row has been skipped, from the syntactic errors to the business ones.
+*/
+pub enum RowRejection {
+    Malformed(String),
+    InvalidRequest(CsvOperationType, String),
+    InvalidOperation(Vec<OperationValidationError>)
+}
+
+impl Display for RowRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RowRejection::Malformed(error) => write!(f, "Error while deserializing row - Error: {error}"),
+            RowRejection::InvalidRequest(_, error_description) => write!(f, "Error while converting row to Operation Request - {error_description}"),
+            RowRejection::InvalidOperation(errors) => {
+                let errors_as_string  = errors.iter().map(|error| error.to_string()).collect::<Vec<_>>().join(", ");
+                write!(f, "Impossible to process operation request - Errors: {errors_as_string}")
+            }
+        }
+    }
+}
+
+/*
+  The rejection carries the operation it was about, as far as it's known, so that it can be correlated even when the row and operation spans are disabled.
+*/
+pub fn report_rejection(source_name: &str, line: u64, operation_type: Option<CsvOperationType>, maybe_client: Option<AccountId>, maybe_tx: Option<TransactionId>, rejection: &RowRejection) {
+    error!(source = source_name, line, operation_type = operation_type.map(tracing::field::display), client = maybe_client, tx = maybe_tx, reason = %rejection, "Row skipped");
+}
+
+/*
+  Processes the inputs one after the other through the same session, each one in the given format or in the one its name suggests.
+*/
+pub async fn process_input_sources(input_sources: Vec<InputSource>, maybe_input_format: Option<InputFormat>, mut processing_session: ProcessingSession) -> Result<ProcessingSession, Box<dyn Error + Send + Sync>> {
+    for input_source in input_sources {
+        let input_format = maybe_input_format.unwrap_or_else(|| InputFormat::from_input_source(&input_source));
+        processing_session = match input_format {
+            InputFormat::Csv => process_from_csv(input_source, processing_session).await.await??,
+            InputFormat::Jsonl => process_from_jsonl(input_source, processing_session).await.await??
+        };
+    }
+    Ok(processing_session)
+}
+
+/*
+  Feeds the engine with a stream of OperationRequest, whatever format they have been read from,
+  so that every format shares the same validation and rejection path.
+  It stops early if the session row limit is reached.
+*/
+pub async fn process_operation_requests(operation_requests: impl Stream<Item = (u64, Result<OperationRequest, RowRejection>)>, source_name: &str, processing_session: &mut ProcessingSession) {
+    let mut operation_requests = std::pin::pin!(operation_requests);
+
+    while !processing_session.is_row_limit_reached() {
+        let Some((line, operation_request_result)) = operation_requests.next().await else {
+            break;
+        };
+
+        processing_session.process_row(source_name, line, operation_request_result);
+    }
+}
+
+#[cfg(test)]
+mod operation_input_test;
@@ -0,0 +1,36 @@
+use crate::domain::Amount;
+use crate::domain::payments_engine::PaymentsEngine;
+use crate::use_case::operation_input::{process_input_sources, InputFormat, InputSource};
+use crate::use_case::processing_session::{ProcessingSession, RejectionReport};
+
+#[test]
+fn test_dash_is_stdin_read_as_csv() {
+    let input_source = InputSource::from_argument("-");
+
+    assert!(matches!(input_source, InputSource::Stdin));
+    assert_eq!(input_source.to_string(), "stdin");
+    assert!(matches!(InputFormat::from_input_source(&input_source), InputFormat::Csv));
+    assert!(matches!(InputFormat::from_input_source(&InputSource::from_argument("operations.ndjson")), InputFormat::Jsonl));
+}
+
+#[tokio::test]
+async fn test_inputs_are_processed_in_order_through_the_same_session() {
+    let rejections_path = std::env::temp_dir().join(format!("multiple_inputs_{}_rejections.csv", std::process::id()));
+    let processing_session = ProcessingSession::new(PaymentsEngine::new())
+        .with_rejection_report(RejectionReport::create(rejections_path.to_str().unwrap()).unwrap());
+    let input_sources = vec![InputSource::from_argument("fixtures/multiple_inputs_1.csv"), InputSource::from_argument("fixtures/multiple_inputs_2.jsonl")];
+
+    let (payments_engine, statistics) = process_input_sources(input_sources, None, processing_session).await.unwrap().finish().unwrap();
+    let rejections = std::fs::read_to_string(&rejections_path).unwrap();
+    std::fs::remove_file(&rejections_path).unwrap();
+
+    // The second input withdraws from and disputes what the first one deposited, and can't reuse its transaction ids
+    assert_eq!(statistics.rows_accepted, 4);
+    assert_eq!(payments_engine.account(1).unwrap().available_amount(), Amount::new(6, 0));
+    assert_eq!(payments_engine.account(2).unwrap().held_amount(), Amount::new(5, 0));
+
+    let rejected_rows: Vec<(&str, &str)> = rejections.lines().skip(1)
+        .map(|rejection| { let mut fields = rejection.split(','); (fields.next().unwrap(), fields.next().unwrap()) })
+        .collect();
+    assert_eq!(rejected_rows, vec![("fixtures/multiple_inputs_1.csv", "3"), ("fixtures/multiple_inputs_2.jsonl", "3")]);
+}
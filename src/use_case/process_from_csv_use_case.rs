@@ -1,19 +1,26 @@
+pub mod operation_csv_fast_reader;
+
 use std::error::Error;
-use csv_async::{AsyncReaderBuilder, Trim};
-use futures::StreamExt;
-use log::error;
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, SeekFrom};
+use std::pin::Pin;
+use csv_async::{AsyncDeserializer, AsyncReaderBuilder, Position, Trim};
+use futures::{stream, AsyncRead, AsyncSeek, Stream, StreamExt};
 use tokio::fs::File;
 use tokio::task::JoinHandle;
 use tokio_util::compat::TokioAsyncReadCompatExt;
+use tracing::Instrument;
 
 use serde::Deserialize;
 use crate::domain::account_module::account::AccountId;
-use crate::domain::Amount;
-use crate::domain::payments_engine::operation_request::OperationRequest;
-use crate::domain::payments_engine::PaymentsEngine;
+use crate::domain::{Amount, Timestamp};
+use crate::domain::payments_engine::operation_request::{ChargebackOperationRequest, DepositOperationRequest, DisputeOperationRequest, OperationRequest, ResolveOperationRequest, WithdrawalOperationRequest};
 use crate::domain::transaction_module::transaction::TransactionId;
+use crate::use_case::operation_input::{process_operation_requests, InputSource, RowRejection};
+use crate::use_case::process_from_csv_use_case::operation_csv_fast_reader::{FastCsvRow, OperationCsvFastReader};
+use crate::use_case::processing_session::ProcessingSession;
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum CsvOperationType {
     Deposit,
@@ -23,57 +30,232 @@ pub enum CsvOperationType {
     Chargeback,
 }
 
+impl CsvOperationType {
+    pub fn of(operation_request: &OperationRequest) -> Self {
+        match operation_request {
+            OperationRequest::Deposit(_) => CsvOperationType::Deposit,
+            OperationRequest::Withdrawal(_) => CsvOperationType::Withdrawal,
+            OperationRequest::Dispute(_) => CsvOperationType::Dispute,
+            OperationRequest::Resolve(_) => CsvOperationType::Resolve,
+            OperationRequest::Chargeback(_) => CsvOperationType::Chargeback
+        }
+    }
+}
+
+impl Display for CsvOperationType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let operation_type = match self {
+            CsvOperationType::Deposit => "deposit",
+            CsvOperationType::Withdrawal => "withdrawal",
+            CsvOperationType::Dispute => "dispute",
+            CsvOperationType::Resolve => "resolve",
+            CsvOperationType::Chargeback => "chargeback"
+        };
+        write!(f, "{operation_type}")
+    }
+}
+
 #[derive(Deserialize, Clone)]
 pub struct OperationCsvRow {
     #[serde(rename = "type")]
     pub operation_type: CsvOperationType,
     pub client: AccountId,
     pub tx: TransactionId,
-    pub amount: Option<Amount>
+    pub amount: Option<Amount>,
+    /*
+      Optional RFC 3339 timestamp (e.g. 2024-03-01T10:15:00Z). The column itself can be missing from the file.
+    */
+    #[serde(default)]
+    pub timestamp: Option<Timestamp>
 }
 
+impl TryFrom<OperationCsvRow> for OperationRequest {
+    type Error = String;
+
+    fn try_from(operation_csv_row: OperationCsvRow) -> Result<Self, Self::Error> {
+        match operation_csv_row.operation_type {
+
+            CsvOperationType::Deposit => {
+                if let Some(amount) = operation_csv_row.amount {
+                    Ok(OperationRequest::Deposit(DepositOperationRequest {
+                        account_id: operation_csv_row.client,
+                        transaction_id: operation_csv_row.tx,
+                        amount: amount.trunc_with_scale(4),
+                        timestamp: operation_csv_row.timestamp
+                    }))
+                } else {
+                    Err("Amount not found for Deposit transaction_module request".to_owned())
+                }
+            }
+            CsvOperationType::Withdrawal => {
+                if let Some(amount) = operation_csv_row.amount {
+                    Ok(OperationRequest::Withdrawal(WithdrawalOperationRequest {
+                        account_id: operation_csv_row.client,
+                        transaction_id: operation_csv_row.tx,
+                        amount: amount.trunc_with_scale(4),
+                        timestamp: operation_csv_row.timestamp
+                    }))
+                } else {
+                    Err("Amount not found for Withdrawal transaction_module request".to_owned())
+                }
+            }
+            CsvOperationType::Dispute => {
+                Ok(OperationRequest::Dispute(DisputeOperationRequest{transaction_id: operation_csv_row.tx, timestamp: operation_csv_row.timestamp}))
+            }
+            CsvOperationType::Resolve => {
+                Ok(OperationRequest::Resolve(ResolveOperationRequest{transaction_id: operation_csv_row.tx, timestamp: operation_csv_row.timestamp}))
+            }
+            CsvOperationType::Chargeback => {
+                Ok(OperationRequest::Chargeback(ChargebackOperationRequest{transaction_id: operation_csv_row.tx, timestamp: operation_csv_row.timestamp}))
+            }
+        }
+    }
+}
 
 
 /*
-  This method expects a CSV path, reads it row by row and produces an OperationRequest, needed by the
+  This method expects a CSV source, reads it row by row and produces an OperationRequest, needed by the
   generic PaymentsEngine.
-  If there is an error, it's just printed.
+  The session (and so the engine) is passed in and returned back, so that multiple sources can be processed in order against the same engine.
+  If there is an error, it's just printed, together with the source name and the line of the row.
+  The spawned task runs in a span of the input, the parent of the spans of its rows.
 */
-pub async fn process_from_csv(file_path: String, mut payments_engine: PaymentsEngine) -> JoinHandle<Result<PaymentsEngine, Box<dyn Error + Send + Sync>>> {
+pub async fn process_from_csv(input_source: InputSource, mut processing_session: ProcessingSession) -> JoinHandle<Result<ProcessingSession, Box<dyn Error + Send + Sync>>> {
+    let input_span = tracing::info_span!("process_from_csv", source = %input_source);
     tokio::spawn ( async move {
 
-        let file = File::open(file_path).await?;
-        let reader = file.compat();
+        let source_name = input_source.to_string();
+
+        match input_source {
+            InputSource::Stdin => process_operation_requests(read_operation_requests_from_csv(tokio::io::stdin().compat()), &source_name, &mut processing_session).await,
+            InputSource::File(file_path) => {
+                let csv_bytes = open_csv_bytes(&file_path)?;
+                let reference_file = File::open(file_path).await?;
+                process_operation_requests(read_operation_requests_from_csv_bytes(csv_bytes, reference_file.compat()), &source_name, &mut processing_session).await
+            }
+        }
+
+        Ok(processing_session)
+    }.instrument(input_span))
+}
 
-        let mut csv_reader = AsyncReaderBuilder::new()
-            .trim(Trim::All)
-            .create_deserializer(reader);
+/*
+  Turns any CSV reader into a stream of OperationRequest, each one paired with the line it comes from.
+  It's shared by every CSV based entry point, so that they all accept and reject rows in the same way.
+*/
+pub fn read_operation_requests_from_csv<R: AsyncRead + Unpin + Send + 'static>(reader: R) -> impl Stream<Item = (u64, Result<OperationRequest, RowRejection>)> {
+    AsyncReaderBuilder::new()
+        .trim(Trim::All)
+        .create_deserializer(reader)
+        .into_deserialize_with_pos::<OperationCsvRow>()
+        .map(|(record, position)| (position.line(), operation_request_from_csv_record(record)))
+}
 
+fn operation_request_from_csv_record(record: Result<OperationCsvRow, csv_async::Error>) -> Result<OperationRequest, RowRejection> {
+    match record {
+        Ok(operation_csv_row) => operation_request_from_csv_row(operation_csv_row),
+        Err(error) => Err(RowRejection::Malformed(error.to_string()))
+    }
+}
 
-        let mut records = csv_reader.deserialize::<OperationCsvRow>();
-        let mut index = 0;
+fn operation_request_from_csv_row(operation_csv_row: OperationCsvRow) -> Result<OperationRequest, RowRejection> {
+    let operation_type = operation_csv_row.operation_type;
+    OperationRequest::try_from(operation_csv_row).map_err(|error_description| RowRejection::InvalidRequest(operation_type, error_description))
+}
 
-        while let Some(record) = records.next().await {
-            match record {
-                Ok(operation_csv_row) => {
-                    let operation_request_result = OperationRequest::new_from_csv(operation_csv_row);
-                    match operation_request_result {
-                        Ok(operation_request) => payments_engine.process(operation_request, index),
-                        Err(error_description) => {
-                            error!("Error while converting CSV row to Operation Request at index {index} - {error_description}");
-                        }
+/*
+  The same stream as read_operation_requests_from_csv, read by the OperationCsvFastReader for speed.
+  The rows the fast reader can't decode are read by csv_async from the reference reader, which must have the same content as csv_bytes
+  (in practice the same file, opened twice): it's sought to each of those rows, or to where the fast reader gave up,
+  so every row is accepted or rejected exactly like read_operation_requests_from_csv does, with the same error messages.
+*/
+pub fn read_operation_requests_from_csv_bytes<B, R>(csv_bytes: B, reference_reader: R) -> impl Stream<Item = (u64, Result<OperationRequest, RowRejection>)>
+where
+    B: BufRead + Send + 'static,
+    R: AsyncRead + AsyncSeek + Unpin + Send + 'static
+{
+    let reference_deserializer = Box::new(AsyncReaderBuilder::new().trim(Trim::All).create_deserializer(reference_reader));
+
+    stream::unfold(CsvBytesReading::Fast(OperationCsvFastReader::new(csv_bytes), reference_deserializer), |csv_bytes_reading| async move {
+        match csv_bytes_reading {
+            CsvBytesReading::Fast(mut fast_reader, mut reference_deserializer) => {
+                match fast_reader.next()? {
+                    FastCsvRow::Decoded(line, operation_request_result) => Some(((line, operation_request_result), CsvBytesReading::Fast(fast_reader, reference_deserializer))),
+                    FastCsvRow::Deferred(position) => {
+                        let row = read_deferred_row(&mut reference_deserializer, position).await;
+                        Some((row, CsvBytesReading::Fast(fast_reader, reference_deserializer)))
+                    }
+                    FastCsvRow::HandedOver(position) => {
+                        let mut rows = hand_over(*reference_deserializer, position).await;
+                        let row = rows.next().await?;
+                        Some((row, CsvBytesReading::HandedOver(rows)))
                     }
                 }
-                Err(error) => {
-                    error!("Error while deserializing CSV row at index {index} - Error: {error}")
-                }
             }
-            index += 1;
+            CsvBytesReading::HandedOver(mut rows) => {
+                let row = rows.next().await?;
+                Some((row, CsvBytesReading::HandedOver(rows)))
+            }
         }
-
-        Ok(payments_engine)
     })
 }
 
+type CsvRows = Pin<Box<dyn Stream<Item = (u64, Result<OperationRequest, RowRejection>)> + Send>>;
+
+enum CsvBytesReading<B, R> {
+    Fast(OperationCsvFastReader<B>, Box<AsyncDeserializer<R>>),
+    HandedOver(CsvRows)
+}
+
+async fn read_deferred_row<R: AsyncRead + AsyncSeek + Unpin + Send>(reference_deserializer: &mut AsyncDeserializer<R>, position: Position) -> (u64, Result<OperationRequest, RowRejection>) {
+    let line = position.line();
+    if let Err(error) = reference_deserializer.seek_raw(SeekFrom::Start(position.byte()), position).await {
+        return (line, Err(RowRejection::Malformed(error.to_string())));
+    }
+    match reference_deserializer.deserialize_with_pos::<OperationCsvRow>().next().await {
+        Some((record, position)) => (position.line(), operation_request_from_csv_record(record)),
+        None => (line, Err(RowRejection::Malformed("Row not found by the CSV reader".to_owned())))
+    }
+}
+
+/*
+  Before the first record there's nothing to seek to: the reference reader hasn't read anything yet, header included.
+*/
+async fn hand_over<R: AsyncRead + AsyncSeek + Unpin + Send + 'static>(mut reference_deserializer: AsyncDeserializer<R>, position: Position) -> CsvRows {
+    let line = position.line();
+    if position.record() > 0 && let Err(error) = reference_deserializer.seek_raw(SeekFrom::Start(position.byte()), position).await {
+        return Box::pin(stream::iter([(line, Err(RowRejection::Malformed(error.to_string())))]));
+    }
+    Box::pin(reference_deserializer.into_deserialize_with_pos::<OperationCsvRow>().map(|(record, position)| (position.line(), operation_request_from_csv_record(record))))
+}
+
+/*
+  With the mmap feature the file is memory mapped, so the fast reader decodes it without even copying it into a buffer.
+*/
+#[cfg(not(feature = "mmap"))]
+fn open_csv_bytes(file_path: &str) -> std::io::Result<std::io::BufReader<std::fs::File>> {
+    Ok(std::io::BufReader::with_capacity(CSV_BUFFER_SIZE, std::fs::File::open(file_path)?))
+}
+
+#[cfg(not(feature = "mmap"))]
+const CSV_BUFFER_SIZE: usize = 256 * 1024;
+
+#[cfg(feature = "mmap")]
+fn open_csv_bytes(file_path: &str) -> std::io::Result<std::io::Cursor<memmap2::Mmap>> {
+    let file = std::fs::File::open(file_path)?;
+    /*
+      Safety: the file must not be truncated or modified while it's processed, which is already the case for any input file
+      (a concurrent change would make the outcome meaningless anyway).
+    */
+    let mmap = unsafe { memmap2::MmapOptions::new().populate().map(&file)? };
+    Ok(std::io::Cursor::new(mmap))
+}
+
 #[cfg(test)]
 mod process_from_csv_use_case_test;
+
+#[cfg(test)]
+mod process_from_csv_use_case_property_test;
+
+#[cfg(test)]
+mod process_from_csv_use_case_golden_test;
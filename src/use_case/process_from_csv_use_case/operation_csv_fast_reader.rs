@@ -0,0 +1,304 @@
+use std::io::BufRead;
+use std::str::FromStr;
+use csv_async::Position;
+use memchr::memchr;
+
+use crate::domain::{Amount, Timestamp};
+use crate::domain::payments_engine::operation_request::OperationRequest;
+use crate::use_case::operation_input::RowRejection;
+use crate::use_case::process_from_csv_use_case::{operation_request_from_csv_row, CsvOperationType, OperationCsvRow};
+
+/*
+  What the fast reader makes of a CSV record:
+    - Decoded: the row has been decoded, with the line csv_async would report for it
+    - Deferred: the row is valid CSV but it's not in the simple form the fast reader decodes (it may well be wrong),
+      so it has to be read by csv_async at the given position, to get exactly its outcome and its error message
+    - HandedOver: from the given position on, the file isn't line oriented anymore (quoted fields, lone \r)
+      or the header isn't the expected one, so csv_async has to read the rest of it. Nothing else is returned afterwards
+*/
+pub enum FastCsvRow {
+    Decoded(u64, Result<OperationRequest, RowRejection>),
+    Deferred(Position),
+    HandedOver(Position)
+}
+
+/*
+  Where every column of OperationCsvRow is in the file: the header is matched by name, like serde does, and unknown columns are skipped.
+*/
+struct CsvColumns {
+    field_count: usize,
+    operation_type: usize,
+    client: usize,
+    tx: usize,
+    amount: Option<usize>,
+    timestamp: Option<usize>
+}
+
+/*
+  A synchronous reader for the plain CSV files the engine gets almost all the time: one record per line, fields separated by commas,
+  no quotes. Every line is decoded in place, straight from the buffer of the BufRead (a memory mapped file is never copied),
+  without going through csv records and serde. It accepts exactly what read_operation_requests_from_csv accepts, with the same trimming,
+  and everything it isn't sure about is returned as Deferred or HandedOver, for csv_async to read it.
+  It also keeps track of the position csv_async would be at, so that csv_async can be sought there.
+*/
+pub struct OperationCsvFastReader<B> {
+    csv_bytes: B,
+    line_buffer: Vec<u8>,
+    line_decoder: CsvLineDecoder
+}
+
+impl<B: BufRead> OperationCsvFastReader<B> {
+    pub fn new(csv_bytes: B) -> Self {
+        OperationCsvFastReader { csv_bytes, line_buffer: vec![], line_decoder: CsvLineDecoder::new() }
+    }
+}
+
+impl<B: BufRead> Iterator for OperationCsvFastReader<B> {
+    type Item = FastCsvRow;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.line_decoder.handed_over {
+            let Ok(available_bytes) = self.csv_bytes.fill_buf() else {
+                return Some(self.line_decoder.hand_over());
+            };
+            if available_bytes.is_empty() {
+                return None;
+            }
+
+            /*
+              Lines are decoded right in the buffer. Only a line cut by the end of the buffer is copied, to read the rest of it.
+            */
+            let fast_csv_row = if let Some(newline_index) = memchr(b'\n', available_bytes) {
+                let fast_csv_row = self.line_decoder.decode_line(&available_bytes[..=newline_index]);
+                self.csv_bytes.consume(newline_index + 1);
+                fast_csv_row
+            } else {
+                self.line_buffer.clear();
+                if self.csv_bytes.read_until(b'\n', &mut self.line_buffer).is_err() {
+                    return Some(self.line_decoder.hand_over());
+                }
+                self.line_decoder.decode_line(&self.line_buffer)
+            };
+
+            if fast_csv_row.is_some() {
+                return fast_csv_row;
+            }
+        }
+        None
+    }
+}
+
+struct CsvLineDecoder {
+    columns: Option<CsvColumns>,
+    // Where the next line starts, in bytes, and its number
+    line_start_byte: u64,
+    line_number: u64,
+    // Where csv_async starts reading the next record from: the end of the previous one, empty lines included
+    record_position: Position,
+    handed_over: bool
+}
+
+impl CsvLineDecoder {
+    fn new() -> Self {
+        let mut record_position = Position::new();
+        record_position.set_line(1);
+        CsvLineDecoder { columns: None, line_start_byte: 0, line_number: 1, record_position, handed_over: false }
+    }
+
+    fn hand_over(&mut self) -> FastCsvRow {
+        self.handed_over = true;
+        FastCsvRow::HandedOver(self.record_position.clone())
+    }
+
+    /*
+      Empty lines are skipped, like csv_async does, so they give no row.
+    */
+    fn decode_line(&mut self, line: &[u8]) -> Option<FastCsvRow> {
+        let (content, terminator_length) = match line {
+            [content @ .., b'\r', b'\n'] => (content, 2),
+            [content @ .., b'\n'] => (content, 1),
+            content => (content, 0)
+        };
+
+        if content.contains(&b'"') || content.contains(&b'\r') {
+            return Some(self.hand_over());
+        }
+
+        let line_start_byte = self.line_start_byte;
+        let line_number = self.line_number;
+        self.line_start_byte += line.len() as u64;
+        self.line_number += u64::from(terminator_length > 0);
+        if content.is_empty() {
+            return None;
+        }
+
+        /*
+          csv_async ends a record at \r, so it only gets to the \n of \r\n (and counts the line) when it reads the next record.
+        */
+        let position = self.record_position.clone();
+        let record_end_byte = if terminator_length == 2 { line_start_byte + line.len() as u64 - 1 } else { line_start_byte + line.len() as u64 };
+        let record_end_line = if terminator_length == 1 { line_number + 1 } else { line_number };
+        self.record_position.set_byte(record_end_byte).set_line(record_end_line).set_record(position.record() + 1);
+
+        match &self.columns {
+            None => {
+                match CsvColumns::from_header(content) {
+                    Some(columns) => {
+                        self.columns = Some(columns);
+                        None
+                    }
+                    None => {
+                        self.handed_over = true;
+                        Some(FastCsvRow::HandedOver(position))
+                    }
+                }
+            }
+            Some(columns) => {
+                match decode_operation_csv_row(content, columns) {
+                    Some(operation_csv_row) => Some(FastCsvRow::Decoded(position.line(), operation_request_from_csv_row(operation_csv_row))),
+                    None => Some(FastCsvRow::Deferred(position))
+                }
+            }
+        }
+    }
+}
+
+impl CsvColumns {
+    /*
+      Only a header with every required column exactly once is accepted: anything else makes every row fail in csv_async,
+      with errors the fast reader doesn't reproduce.
+    */
+    fn from_header(header: &[u8]) -> Option<Self> {
+        if !header.is_ascii() {
+            return None;
+        }
+
+        let (mut operation_type, mut client, mut tx, mut amount, mut timestamp) = (None, None, None, None, None);
+        let mut field_count = 0;
+        for (index, name) in header.split(|&byte| byte == b',').enumerate() {
+            field_count += 1;
+            let column = match trim(name) {
+                b"type" => &mut operation_type,
+                b"client" => &mut client,
+                b"tx" => &mut tx,
+                b"amount" => &mut amount,
+                b"timestamp" => &mut timestamp,
+                _ => continue
+            };
+            if column.replace(index).is_some() {
+                return None;
+            }
+        }
+
+        Some(CsvColumns { field_count, operation_type: operation_type?, client: client?, tx: tx?, amount, timestamp })
+    }
+}
+
+fn decode_operation_csv_row(record: &[u8], columns: &CsvColumns) -> Option<OperationCsvRow> {
+    if !record.is_ascii() {
+        return None;
+    }
+
+    let (mut operation_type, mut client, mut tx, mut amount, mut timestamp) = (None, None, None, &b""[..], &b""[..]);
+    let mut field_count = 0;
+    for (index, field) in record.split(|&byte| byte == b',').enumerate() {
+        field_count += 1;
+        let field = trim(field);
+        if index == columns.operation_type {
+            operation_type = Some(parse_operation_type(field)?);
+        } else if index == columns.client {
+            client = Some(parse_unsigned(field)?);
+        } else if index == columns.tx {
+            tx = Some(parse_unsigned(field)?);
+        } else if Some(index) == columns.amount {
+            amount = field;
+        } else if Some(index) == columns.timestamp {
+            timestamp = field;
+        }
+    }
+    if field_count != columns.field_count {
+        return None;
+    }
+
+    Some(OperationCsvRow {
+        operation_type: operation_type?,
+        client: u16::try_from(client?).ok()?,
+        tx: u32::try_from(tx?).ok()?,
+        amount: if amount.is_empty() { None } else { Some(parse_amount(amount)?) },
+        timestamp: if timestamp.is_empty() { None } else { Some(Timestamp::from_str(std::str::from_utf8(timestamp).ok()?).ok()?) }
+    })
+}
+
+/*
+  The ASCII characters str::trim removes: the lines are checked to be ASCII before being trimmed.
+*/
+fn trim(field: &[u8]) -> &[u8] {
+    let is_whitespace = |byte: &u8| matches!(byte, b'\t' | b'\n' | b'\x0B' | b'\x0C' | b'\r' | b' ');
+    let start = field.iter().position(|byte| !is_whitespace(byte)).unwrap_or(field.len());
+    let end = field.iter().rposition(|byte| !is_whitespace(byte)).map_or(start, |index| index + 1);
+    &field[start..end]
+}
+
+fn parse_operation_type(field: &[u8]) -> Option<CsvOperationType> {
+    match field {
+        b"deposit" => Some(CsvOperationType::Deposit),
+        b"withdrawal" => Some(CsvOperationType::Withdrawal),
+        b"dispute" => Some(CsvOperationType::Dispute),
+        b"resolve" => Some(CsvOperationType::Resolve),
+        b"chargeback" => Some(CsvOperationType::Chargeback),
+        _ => None
+    }
+}
+
+/*
+  Just digits: the signs and the hexadecimal notation csv_async also accepts are left to it.
+*/
+fn parse_unsigned(field: &[u8]) -> Option<u64> {
+    if field.is_empty() {
+        return None;
+    }
+    field.iter().try_fold(0u64, |number, &byte| {
+        if byte.is_ascii_digit() { number.checked_mul(10)?.checked_add(u64::from(byte - b'0')) } else { None }
+    })
+}
+
+/*
+  csv_async doesn't hand the text of the amount to Decimal: an integer gets to it as an i64 or u64, anything else with a fractional part
+  as an f64, which Decimal prints back and parses. Only the amounts where that is lossless are decoded here:
+  integers within the i64 and u64 ranges, and decimals with at most 15 digits, that an f64 always holds exactly.
+  The f64 is printed without trailing zeros, hence the normalize.
+*/
+#[cfg(not(feature = "fixed_point_amount"))]
+fn parse_amount(field: &[u8]) -> Option<Amount> {
+    const F64_EXACT_DIGITS: usize = 15;
+
+    let (negative, unsigned_field) = match field {
+        [b'-', unsigned_field @ ..] => (true, unsigned_field),
+        unsigned_field => (false, unsigned_field)
+    };
+    let Some(dot_index) = memchr(b'.', unsigned_field) else {
+        let integer = parse_unsigned(unsigned_field)?;
+        return if negative { i64::try_from(integer).ok().filter(|&integer| integer != 0).map(|integer| Amount::from(-integer)) } else { Some(Amount::from(integer)) };
+    };
+
+    let (integer_part, fractional_part) = (&unsigned_field[..dot_index], &unsigned_field[dot_index + 1..]);
+    if integer_part.is_empty() || fractional_part.is_empty() || integer_part.len() + fractional_part.len() > F64_EXACT_DIGITS {
+        return None;
+    }
+    let mantissa = (parse_unsigned(integer_part)? * 10u64.pow(fractional_part.len() as u32) + parse_unsigned(fractional_part)?) as i64;
+    if mantissa == 0 && negative {
+        return None;
+    }
+    Some(Amount::new(if negative { -mantissa } else { mantissa }, fractional_part.len() as u32).normalize())
+}
+
+/*
+  With fixed point amounts csv_async hands over the text itself, and the fast reader does the same parsing.
+*/
+#[cfg(feature = "fixed_point_amount")]
+fn parse_amount(field: &[u8]) -> Option<Amount> {
+    Amount::from_str(std::str::from_utf8(field).ok()?).ok()
+}
+
+#[cfg(test)]
+mod operation_csv_fast_reader_test;
@@ -0,0 +1,171 @@
+use std::io::BufReader;
+use std::path::Path;
+use futures::StreamExt;
+use proptest::prelude::*;
+use crate::domain::payments_engine::operation_request::OperationRequest;
+use crate::use_case::operation_input::RowRejection;
+use crate::use_case::process_from_csv_use_case::{read_operation_requests_from_csv, read_operation_requests_from_csv_bytes, CsvOperationType};
+
+/*
+  The fast reader is only correct if it's indistinguishable from csv_async: every input is read both ways and every row,
+  with its line, its request or its rejection message, must be the same.
+  A tiny buffer makes sure that lines cut by the end of the buffer are read like the other ones.
+*/
+fn describe_rows(rows: Vec<(u64, Result<OperationRequest, RowRejection>)>) -> Vec<String> {
+    rows.into_iter()
+        .map(|(line, operation_request_result)| match operation_request_result {
+            Ok(operation_request) => format!("{line}: {}", describe_operation_request(&operation_request)),
+            Err(RowRejection::InvalidRequest(operation_type, error_description)) => format!("{line}: {operation_type} {error_description}"),
+            Err(rejection) => format!("{line}: {rejection}")
+        })
+        .collect()
+}
+
+fn describe_operation_request(operation_request: &OperationRequest) -> String {
+    let operation_type = CsvOperationType::of(operation_request);
+    match operation_request {
+        OperationRequest::Deposit(deposit) => format!("{operation_type} {} {} {} {:?}", deposit.account_id, deposit.transaction_id, deposit.amount, deposit.timestamp),
+        OperationRequest::Withdrawal(withdrawal) => format!("{operation_type} {} {} {} {:?}", withdrawal.account_id, withdrawal.transaction_id, withdrawal.amount, withdrawal.timestamp),
+        _ => format!("{operation_type} {} {:?}", operation_request.transaction_id(), operation_request.timestamp())
+    }
+}
+
+fn read_both_ways(csv: &[u8], buffer_capacity: usize) -> (Vec<String>, Vec<String>) {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    runtime.block_on(async {
+        let reference_rows = read_operation_requests_from_csv(futures::io::Cursor::new(csv.to_vec())).collect().await;
+        let fast_csv_bytes = BufReader::with_capacity(buffer_capacity, std::io::Cursor::new(csv.to_vec()));
+        let fast_rows = read_operation_requests_from_csv_bytes(fast_csv_bytes, futures::io::Cursor::new(csv.to_vec())).collect().await;
+        (describe_rows(reference_rows), describe_rows(fast_rows))
+    })
+}
+
+fn assert_same_rows(csv: impl AsRef<[u8]>) {
+    let csv = csv.as_ref();
+    for buffer_capacity in [1, 7, 8 * 1024] {
+        let (reference_rows, fast_rows) = read_both_ways(csv, buffer_capacity);
+        assert_eq!(fast_rows, reference_rows, "input:\n{:?}", String::from_utf8_lossy(csv));
+    }
+}
+
+#[test]
+fn test_fast_reader_reads_like_csv_async() {
+    let header = "type, client ,tx,amount\n";
+    let rows = [
+        "deposit,1,1,1.0",
+        " withdrawal\t, 2 , 3 ,  0.12345 ",
+        "dispute,1,1,",
+        "resolve, 1, 1, ",
+        "chargeback,1,1,",
+        "deposit,1,1,",
+        "deposit,1,2,100",
+        "deposit,1,2,-100",
+        "deposit,1,2,-0",
+        "deposit,1,2,-0.0",
+        "deposit,1,2,+1.5",
+        "deposit,1,2,.5",
+        "deposit,1,2,5.",
+        "deposit,1,2,1.50",
+        "deposit,1,2,0.0000000000001",
+        "deposit,1,2,0.99999999999999999999",
+        "deposit,1,2,123456789012345.6789",
+        "deposit,1,2,18446744073709551615",
+        "deposit,1,2,18446744073709551616",
+        "deposit,1,2,1e3",
+        "deposit,1,2,inf",
+        "deposit,1,2,true",
+        "deposit,1,2,abc",
+        "deposit,0x1F,2,1",
+        "deposit,+1,+2,1",
+        "deposit,-1,2,1",
+        "deposit,65536,2,1",
+        "deposit,1,4294967296,1",
+        "Deposit,1,2,1",
+        "deposit,1,2",
+        "deposit,1,2,3,4",
+        "   ",
+        "\u{a0}deposit,1,2,1\u{3000}",
+        "deposit,1,2,1\x0B",
+    ];
+    for row in rows {
+        assert_same_rows(format!("{header}{row}\n"));
+    }
+    assert_same_rows(format!("{header}{}\n", rows.join("\n")));
+    assert_same_rows(format!("{}{}\r\n", header.replace('\n', "\r\n"), rows.join("\r\n\r\n")));
+}
+
+#[test]
+fn test_fast_reader_hands_over_what_is_not_line_oriented() {
+    assert_same_rows("");
+    assert_same_rows("\n\n");
+    assert_same_rows("\n\ntype,client,tx,amount\n\n\ndeposit,1,1,1\n\ndeposit,1,2,1");
+    assert_same_rows("type,client,tx,amount\ndeposit,1,1,1\n\"deposit\",1,2,\"1.5\"\ndeposit,1,3,\"1\n5\"\ndeposit,1,4,1\n");
+    assert_same_rows("type,client,tx,amount\rdeposit,1,1,1\rdeposit,1,2,1\r");
+    assert_same_rows("type,client,tx,amount\ndeposit,1,1,1\ndeposit,1,2,1\rdeposit,1,3,1\n");
+    assert_same_rows("type,client,amount\ndeposit,1,1\n");
+    assert_same_rows("type,client,tx,amount,tx\ndeposit,1,1,1,2\n");
+    assert_same_rows("Type,client,tx,amount\ndeposit,1,1,1\n");
+    assert_same_rows("\u{feff}type,client,tx,amount\ndeposit,1,1,1\n");
+    assert_same_rows("tx,type,note,client\n1,deposit,whatever,1\n2,withdrawal,,1\n");
+    assert_same_rows("type,client,tx,amount,timestamp\ndeposit,1,1,1, 2024-03-01T10:15:00Z \ndeposit,1,2,1,2024-03-01T10:15:00+02:00\ndeposit,1,3,1,yesterday\ndeposit,1,4,1,\n");
+    assert_same_rows(b"type,client,tx,amount\ndeposit,1,1,1\xff\ndeposit,1,2,1\n");
+}
+
+#[test]
+fn test_fast_reader_reads_the_fixtures_like_csv_async() {
+    let mut fixture_paths = vec![];
+    let mut directories = vec![Path::new("fixtures").to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                directories.push(path);
+            } else if path.extension().is_some_and(|extension| extension == "csv") {
+                fixture_paths.push(path);
+            }
+        }
+    }
+    assert!(!fixture_paths.is_empty());
+
+    for fixture_path in fixture_paths {
+        assert_same_rows(std::fs::read(&fixture_path).unwrap());
+    }
+}
+
+/*
+  Rows assembled from fields that are valid, almost valid or plainly wrong, with all sorts of whitespace and line endings.
+*/
+fn generated_field() -> impl Strategy<Value = String> {
+    prop_oneof![
+        4 => prop_oneof![Just("deposit"), Just("withdrawal"), Just("dispute"), Just("resolve"), Just("chargeback"), Just("Deposit"), Just("")].prop_map(str::to_owned),
+        4 => (0u64..70_000).prop_map(|number| number.to_string()),
+        4 => (-1_000_000i64..1_000_000_000, 0u32..8).prop_map(|(mantissa, scale)| rust_decimal::Decimal::new(mantissa, scale).to_string()),
+        1 => "[-+]?[0-9]{0,22}(\\.[0-9]{0,22})?",
+        1 => "[0-9a-zA-Z.+\\-\"\u{a0}]{0,6}",
+    ]
+}
+
+fn generated_csv() -> impl Strategy<Value = String> {
+    let header = prop_oneof![
+        4 => Just("type,client,tx,amount".to_owned()),
+        1 => Just("type,client,tx,amount,timestamp".to_owned()),
+        1 => Just("client,type,extra,amount,tx".to_owned()),
+        1 => Just("type,client,tx".to_owned()),
+    ];
+    let padding = prop_oneof![4 => Just(""), 1 => Just(" "), 1 => Just("\t"), 1 => Just(" \x0C ")];
+    let field = (padding.clone(), generated_field(), padding).prop_map(|(before, field, after)| format!("{before}{field}{after}"));
+    let row = prop::collection::vec(field, 2..6).prop_map(|fields| fields.join(","));
+    let line_ending = prop_oneof![6 => Just("\n"), 2 => Just("\r\n"), 1 => Just("\n\n"), 1 => Just("\r\n\r\n"), 1 => Just("\r")];
+    let line = (row, line_ending).prop_map(|(row, line_ending)| format!("{row}{line_ending}"));
+    (header, prop::collection::vec(line, 0..12)).prop_map(|(header, lines)| format!("{header}\n{}", lines.concat()))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1000))]
+
+    #[test]
+    fn test_fast_reader_matches_csv_async(csv in generated_csv(), buffer_capacity in 1usize..40) {
+        let (reference_rows, fast_rows) = read_both_ways(csv.as_bytes(), buffer_capacity);
+        prop_assert_eq!(fast_rows, reference_rows, "input:\n{:?}", csv);
+    }
+}
@@ -0,0 +1,107 @@
+use std::path::{Path, PathBuf};
+use crate::domain::payments_engine::PaymentsEngine;
+use crate::domain::payments_engine::engine_policy::EnginePolicy;
+use crate::output_printer::{write_outcome, OutputSettings};
+use crate::use_case::operation_input::InputSource;
+use crate::use_case::process_from_csv_use_case::process_from_csv;
+use crate::use_case::processing_session::{ProcessingSession, RejectionReport};
+
+/*
+  Data-driven scenarios: every fixtures/<name>/input.csv is processed and the resulting accounts are compared with the sibling expected.csv,
+  and the rejected rows with expected_rejections.csv, when present. An optional config.toml holds the engine policy of the scenario.
+  Adding a scenario is just adding a folder. Run with REGENERATE_GOLDEN_FILES=1 to write the expectations from the current outcome,
+  then review them with git diff.
+*/
+const REGENERATE_GOLDEN_FILES_VARIABLE: &str = "REGENERATE_GOLDEN_FILES";
+
+struct GoldenOutcome {
+    accounts: String,
+    rejections: String
+}
+
+#[tokio::test]
+async fn test_golden_fixtures() {
+    let regenerate = std::env::var_os(REGENERATE_GOLDEN_FILES_VARIABLE).is_some();
+    let scenario_paths = find_scenarios(Path::new("fixtures"));
+    assert!(!scenario_paths.is_empty(), "No fixtures/<name>/input.csv found");
+
+    let mut failures = vec![];
+    for scenario_path in scenario_paths {
+        let golden_outcome = run_scenario(&scenario_path).await;
+        let expected_accounts_path = scenario_path.join("expected.csv");
+        let expected_rejections_path = scenario_path.join("expected_rejections.csv");
+
+        if regenerate {
+            std::fs::write(&expected_accounts_path, &golden_outcome.accounts).unwrap();
+            if expected_rejections_path.exists() || golden_outcome.rejections.lines().count() > 1 {
+                std::fs::write(&expected_rejections_path, &golden_outcome.rejections).unwrap();
+            }
+            continue;
+        }
+
+        failures.extend(compare_with_golden_file(&expected_accounts_path, &golden_outcome.accounts));
+        if expected_rejections_path.exists() {
+            failures.extend(compare_with_golden_file(&expected_rejections_path, &golden_outcome.rejections));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}\nRun with {REGENERATE_GOLDEN_FILES_VARIABLE}=1 to accept the new outcome", failures.join("\n"));
+}
+
+fn find_scenarios(fixtures_path: &Path) -> Vec<PathBuf> {
+    let mut scenario_paths: Vec<PathBuf> = std::fs::read_dir(fixtures_path)
+        .unwrap()
+        .map(|dir_entry| dir_entry.unwrap().path())
+        .filter(|path| path.join("input.csv").is_file())
+        .collect();
+    scenario_paths.sort();
+    scenario_paths
+}
+
+async fn run_scenario(scenario_path: &Path) -> GoldenOutcome {
+    let config_path = scenario_path.join("config.toml");
+    let engine_policy = if config_path.exists() {
+        toml::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap()
+    } else {
+        EnginePolicy::default()
+    };
+
+    let scenario_name = scenario_path.file_name().unwrap().to_string_lossy();
+    let rejections_path = std::env::temp_dir().join(format!("golden_{}_{scenario_name}_rejections.csv", std::process::id()));
+    let processing_session = ProcessingSession::new(PaymentsEngine::with_policy(engine_policy))
+        .with_rejection_report(RejectionReport::create(rejections_path.to_str().unwrap()).unwrap());
+
+    let input_source = InputSource::File(scenario_path.join("input.csv").to_string_lossy().into_owned());
+    let (payments_engine, _) = process_from_csv(input_source, processing_session).await.await.unwrap().unwrap().finish().unwrap();
+
+    let mut accounts = vec![];
+    write_outcome(&mut accounts, payments_engine.accounts(), &OutputSettings::default()).unwrap();
+    let rejections = std::fs::read_to_string(&rejections_path).unwrap();
+    std::fs::remove_file(&rejections_path).unwrap();
+
+    GoldenOutcome { accounts: String::from_utf8(accounts).unwrap(), rejections }
+}
+
+/*
+  Reports the first line that differs, which is usually enough to understand what changed.
+*/
+fn compare_with_golden_file(golden_file_path: &Path, actual: &str) -> Option<String> {
+    let Ok(expected) = std::fs::read_to_string(golden_file_path) else {
+        return Some(format!("{}: missing", golden_file_path.display()));
+    };
+    if expected == actual {
+        return None;
+    }
+
+    let (expected_lines, actual_lines): (Vec<&str>, Vec<&str>) = (expected.lines().collect(), actual.lines().collect());
+    let first_difference = (0..expected_lines.len().max(actual_lines.len()))
+        .find(|&index| expected_lines.get(index) != actual_lines.get(index))
+        .unwrap_or(0);
+    Some(format!(
+        "{}:{}: expected {:?}, got {:?}",
+        golden_file_path.display(),
+        first_difference + 1,
+        expected_lines.get(first_difference).unwrap_or(&"<end of file>"),
+        actual_lines.get(first_difference).unwrap_or(&"<end of file>")
+    ))
+}
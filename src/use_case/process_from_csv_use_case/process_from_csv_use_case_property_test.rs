@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use proptest::prelude::*;
+use crate::domain::Amount;
+use crate::domain::account_module::account::Account;
+use crate::domain::payments_engine::PaymentsEngine;
+use crate::use_case::operation_input::process_operation_requests;
+use crate::use_case::process_from_csv_use_case::read_operation_requests_from_csv;
+use crate::use_case::processing_session::ProcessingSession;
+
+/*
+  Random but plausible operation streams: few clients and transaction ids, so that duplicated ids, disputes on existing
+  and non-existing transactions and operations on frozen clients happen all the time.
+  Every stream is rendered as a CSV and goes through the same pipeline as a file, so a failure is shrunk to a minimal CSV.
+*/
+#[derive(Clone, Copy, Debug)]
+enum GeneratedOperationType {
+    Deposit,
+    Withdrawal,
+    Dispute,
+    Resolve,
+    Chargeback
+}
+
+#[derive(Clone, Debug)]
+struct GeneratedOperation {
+    operation_type: GeneratedOperationType,
+    client: u16,
+    tx: u32,
+    amount: Amount
+}
+
+fn generated_operation() -> impl Strategy<Value = GeneratedOperation> {
+    let operation_type = prop_oneof![
+        3 => Just(GeneratedOperationType::Deposit),
+        2 => Just(GeneratedOperationType::Withdrawal),
+        2 => Just(GeneratedOperationType::Dispute),
+        1 => Just(GeneratedOperationType::Resolve),
+        1 => Just(GeneratedOperationType::Chargeback),
+    ];
+    let amount = (-1_000i64..100_000, 0u32..=4).prop_map(|(mantissa, scale)| Amount::new(mantissa, scale));
+
+    (operation_type, 1u16..=4, 1u32..=12, amount)
+        .prop_map(|(operation_type, client, tx, amount)| GeneratedOperation { operation_type, client, tx, amount })
+}
+
+fn to_csv(generated_operations: &[GeneratedOperation]) -> String {
+    let mut csv = String::from("type,client,tx,amount\n");
+    for generated_operation in generated_operations {
+        let GeneratedOperation { client, tx, amount, .. } = generated_operation;
+        let row = match generated_operation.operation_type {
+            GeneratedOperationType::Deposit => format!("deposit,{client},{tx},{amount}"),
+            GeneratedOperationType::Withdrawal => format!("withdrawal,{client},{tx},{amount}"),
+            GeneratedOperationType::Dispute => format!("dispute,{client},{tx},"),
+            GeneratedOperationType::Resolve => format!("resolve,{client},{tx},"),
+            GeneratedOperationType::Chargeback => format!("chargeback,{client},{tx},"),
+        };
+        csv.push_str(&row);
+        csv.push('\n');
+    }
+    csv
+}
+
+/*
+  The reference model: the rules of the README, written as plainly as possible, without any of the engine types.
+*/
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+struct ModelAccount {
+    available: Amount,
+    held: Amount,
+    locked: bool
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ModelTransactionState {
+    Deposited,
+    Disputed,
+    ChargedBack,
+    Withdrawn
+}
+
+#[derive(Default)]
+struct ReferenceModel {
+    accounts: HashMap<u16, ModelAccount>,
+    transactions: HashMap<u32, (u16, Amount, ModelTransactionState)>,
+    accepted: u64
+}
+
+impl ReferenceModel {
+    fn apply(&mut self, generated_operation: &GeneratedOperation) {
+        let GeneratedOperation { client, tx, amount, .. } = *generated_operation;
+        let accepted = match generated_operation.operation_type {
+            GeneratedOperationType::Deposit => {
+                let account = self.accounts.get(&client).copied().unwrap_or_default();
+                let accepted = !account.locked && !amount.is_sign_negative() && !self.transactions.contains_key(&tx);
+                if accepted {
+                    self.accounts.insert(client, ModelAccount { available: account.available + amount, ..account });
+                    self.transactions.insert(tx, (client, amount, ModelTransactionState::Deposited));
+                }
+                accepted
+            }
+            GeneratedOperationType::Withdrawal => {
+                let accepted = self.accounts.get(&client).is_some_and(|account| !account.locked && !amount.is_sign_negative() && account.available >= amount)
+                    && !self.transactions.contains_key(&tx);
+                if accepted {
+                    self.accounts.entry(client).and_modify(|account| account.available -= amount);
+                    self.transactions.insert(tx, (client, amount, ModelTransactionState::Withdrawn));
+                }
+                accepted
+            }
+            GeneratedOperationType::Dispute => self.move_disputed_amount(tx, ModelTransactionState::Deposited, ModelTransactionState::Disputed),
+            GeneratedOperationType::Resolve => self.move_disputed_amount(tx, ModelTransactionState::Disputed, ModelTransactionState::Deposited),
+            GeneratedOperationType::Chargeback => self.move_disputed_amount(tx, ModelTransactionState::Disputed, ModelTransactionState::ChargedBack)
+        };
+
+        if accepted {
+            self.accepted += 1;
+        }
+    }
+
+    // The client of the row is ignored: disputes, resolves and chargebacks only refer to the transaction
+    fn move_disputed_amount(&mut self, tx: u32, from_state: ModelTransactionState, to_state: ModelTransactionState) -> bool {
+        let Some(&(client, amount, state)) = self.transactions.get(&tx) else {
+            return false;
+        };
+        let Some(account) = self.accounts.get_mut(&client).filter(|account| !account.locked) else {
+            return false;
+        };
+        if state != from_state {
+            return false;
+        }
+
+        match to_state {
+            ModelTransactionState::Disputed => {
+                account.available -= amount;
+                account.held += amount;
+            }
+            ModelTransactionState::Deposited => {
+                account.available += amount;
+                account.held -= amount;
+            }
+            ModelTransactionState::ChargedBack => {
+                account.held -= amount;
+                account.locked = true;
+            }
+            ModelTransactionState::Withdrawn => unreachable!()
+        }
+        self.transactions.insert(tx, (client, amount, to_state));
+        true
+    }
+}
+
+fn process_with_engine(csv: String) -> ProcessingSession {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    runtime.block_on(async {
+        let mut processing_session = ProcessingSession::new(PaymentsEngine::new().with_invariant_checks());
+        let operation_requests = read_operation_requests_from_csv(futures::io::Cursor::new(csv.into_bytes()));
+        process_operation_requests(operation_requests, "generated", &mut processing_session).await;
+        processing_session
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn test_engine_matches_the_reference_model(generated_operations in prop::collection::vec(generated_operation(), 0..60)) {
+        let mut reference_model = ReferenceModel::default();
+        generated_operations.iter().for_each(|generated_operation| reference_model.apply(generated_operation));
+
+        let csv = to_csv(&generated_operations);
+        let (payments_engine, statistics) = process_with_engine(csv.clone()).finish().unwrap();
+
+        prop_assert_eq!(statistics.rows_read, generated_operations.len() as u64, "input:\n{}", csv);
+        prop_assert_eq!(statistics.rows_accepted, reference_model.accepted, "input:\n{}", csv);
+        prop_assert_eq!(statistics.invariant_violations, 0, "input:\n{}", csv);
+
+        let engine_accounts: HashMap<u16, ModelAccount> = payments_engine.accounts()
+            .map(|account| (account.id(), ModelAccount {
+                available: account.available_amount(),
+                held: account.held_amount(),
+                locked: matches!(account, Account::Frozen(_))
+            }))
+            .collect();
+        prop_assert_eq!(engine_accounts, reference_model.accounts, "input:\n{}", csv);
+    }
+}
@@ -1,15 +1,16 @@
-use rust_decimal::Decimal;
-use rust_decimal::prelude::FromPrimitive;
+use crate::domain::Amount;
 use crate::domain::account_module::account::Account;
 use crate::domain::payments_engine::PaymentsEngine;
+use crate::use_case::operation_input::InputSource;
+use crate::use_case::processing_session::ProcessingSession;
 use crate::use_case::process_from_csv_use_case::process_from_csv;
 
 #[tokio::test]
 async fn test_csv_processing_use_case_against_comprehensive_fixture() {
     let payments_engine= PaymentsEngine::new();
 
-    let result = process_from_csv("fixtures/comprehensive_test_with_errors.csv".to_string(), payments_engine).await.await.unwrap().unwrap();
-    let mut resulting_accounts: Vec<&Account> = result.accounts().collect();
+    let result = process_from_csv(InputSource::File("fixtures/comprehensive_test_with_errors.csv".to_string()), ProcessingSession::new(payments_engine)).await.await.unwrap().unwrap();
+    let mut resulting_accounts: Vec<&Account> = result.payments_engine.accounts().collect();
     resulting_accounts.sort_by_key(|account| account.id());
 
     let expected = [
@@ -22,9 +23,9 @@ async fn test_csv_processing_use_case_against_comprehensive_fixture() {
 
     for (account, &(expected_account_id, expected_available_amount, expected_held_amount, expected_total_amount, expected_account_frozen)) in resulting_accounts.iter().zip(&expected) {
         assert_eq!(account.id(), expected_account_id);
-        assert_eq!(account.available_amount(), Decimal::from_f64(expected_available_amount).unwrap());
-        assert_eq!(account.held_amount(), Decimal::from_f64(expected_held_amount).unwrap());
-        assert_eq!(account.total_amount(), Decimal::from_f64(expected_total_amount).unwrap());
+        assert_eq!(account.available_amount(), expected_available_amount.to_string().parse::<Amount>().unwrap());
+        assert_eq!(account.held_amount(), expected_held_amount.to_string().parse::<Amount>().unwrap());
+        assert_eq!(account.total_amount(), expected_total_amount.to_string().parse::<Amount>().unwrap());
         assert_eq!(matches!(account, Account::Frozen(_)), expected_account_frozen);
     }
 
@@ -0,0 +1,90 @@
+use std::error::Error;
+use futures::io::BufReader;
+use futures::{future, AsyncBufReadExt, AsyncRead, Stream, StreamExt};
+use serde::Deserialize;
+use tokio::fs::File;
+use tokio::task::JoinHandle;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tracing::Instrument;
+
+use crate::domain::account_module::account::AccountId;
+use crate::domain::{Amount, Timestamp};
+use crate::domain::payments_engine::operation_request::OperationRequest;
+use crate::domain::transaction_module::transaction::TransactionId;
+use crate::use_case::operation_input::{process_operation_requests, InputSource, RowRejection};
+use crate::use_case::processing_session::ProcessingSession;
+use crate::use_case::process_from_csv_use_case::{CsvOperationType, OperationCsvRow};
+
+/*
+  The JSON counterpart of OperationCsvRow: {"type": "deposit", "client": 1, "tx": 1, "amount": 1.5}
+  The amount can be either a JSON number or a string, and it's parsed exactly in both cases (no float in between).
+*/
+#[derive(Deserialize, Clone)]
+pub struct OperationJsonRow {
+    #[serde(rename = "type")]
+    pub operation_type: CsvOperationType,
+    pub client: AccountId,
+    pub tx: TransactionId,
+    #[cfg_attr(not(feature = "fixed_point_amount"), serde(default, with = "rust_decimal::serde::arbitrary_precision_option"))]
+    #[cfg_attr(feature = "fixed_point_amount", serde(default, with = "crate::domain::fixed_point_amount::arbitrary_precision_option"))]
+    pub amount: Option<Amount>,
+    #[serde(default)]
+    pub timestamp: Option<Timestamp>
+}
+
+impl From<OperationJsonRow> for OperationCsvRow {
+    fn from(operation_json_row: OperationJsonRow) -> Self {
+        OperationCsvRow {
+            operation_type: operation_json_row.operation_type,
+            client: operation_json_row.client,
+            tx: operation_json_row.tx,
+            amount: operation_json_row.amount,
+            timestamp: operation_json_row.timestamp
+        }
+    }
+}
+
+impl OperationJsonRow {
+    pub fn into_operation_request(self) -> Result<OperationRequest, RowRejection> {
+        let operation_type = self.operation_type;
+        OperationRequest::try_from(OperationCsvRow::from(self)).map_err(|error_description| RowRejection::InvalidRequest(operation_type, error_description))
+    }
+}
+
+/*
+  Same as process_from_csv, but every line of the source is a JSON object. Blank lines are ignored.
+*/
+pub async fn process_from_jsonl(input_source: InputSource, mut processing_session: ProcessingSession) -> JoinHandle<Result<ProcessingSession, Box<dyn Error + Send + Sync>>> {
+    let input_span = tracing::info_span!("process_from_jsonl", source = %input_source);
+    tokio::spawn ( async move {
+
+        let source_name = input_source.to_string();
+
+        match input_source {
+            InputSource::Stdin => process_operation_requests(read_operation_requests_from_jsonl(tokio::io::stdin().compat()), &source_name, &mut processing_session).await,
+            InputSource::File(file_path) => {
+                let file = File::open(file_path).await?;
+                process_operation_requests(read_operation_requests_from_jsonl(file.compat()), &source_name, &mut processing_session).await
+            }
+        }
+
+        Ok(processing_session)
+    }.instrument(input_span))
+}
+
+pub fn read_operation_requests_from_jsonl<R: AsyncRead + Unpin + Send + 'static>(reader: R) -> impl Stream<Item = (u64, Result<OperationRequest, RowRejection>)> {
+    BufReader::new(reader)
+        .lines()
+        .enumerate()
+        .filter(|(_, line_result)| future::ready(!matches!(line_result, Ok(line) if line.trim().is_empty())))
+        .map(|(index, line_result)| {
+            let operation_request_result = line_result
+                .map_err(|error| RowRejection::Malformed(error.to_string()))
+                .and_then(|line| serde_json::from_str::<OperationJsonRow>(&line).map_err(|error| RowRejection::Malformed(error.to_string())))
+                .and_then(OperationJsonRow::into_operation_request);
+            (index as u64 + 1, operation_request_result)
+        })
+}
+
+#[cfg(test)]
+mod process_from_jsonl_use_case_test;
@@ -0,0 +1,33 @@
+use std::str::FromStr;
+use crate::domain::Amount;
+use crate::domain::account_module::account::Account;
+use crate::domain::payments_engine::PaymentsEngine;
+use crate::use_case::operation_input::InputSource;
+use crate::use_case::processing_session::ProcessingSession;
+use crate::use_case::process_from_jsonl_use_case::process_from_jsonl;
+
+#[tokio::test]
+async fn test_jsonl_processing_use_case_against_comprehensive_fixture() {
+    let payments_engine= PaymentsEngine::new();
+
+    let result = process_from_jsonl(InputSource::File("fixtures/comprehensive_test_with_errors.jsonl".to_string()), ProcessingSession::new(payments_engine)).await.await.unwrap().unwrap();
+    let mut resulting_accounts: Vec<&Account> = result.payments_engine.accounts().collect();
+    resulting_accounts.sort_by_key(|account| account.id());
+
+    let expected = [
+        (1, "-0.5",              "0",  "-0.5",              true),
+        (2, "2.0",               "0",  "2.0",               false),
+        (3, "1000000000000.0001", "0", "1000000000000.0001", false),
+        (4, "123456789.1234",    "0",  "123456789.1234",    false),
+    ];
+
+    for (account, &(expected_account_id, expected_available_amount, expected_held_amount, expected_total_amount, expected_account_frozen)) in resulting_accounts.iter().zip(&expected) {
+        assert_eq!(account.id(), expected_account_id);
+        assert_eq!(account.available_amount(), Amount::from_str(expected_available_amount).unwrap());
+        assert_eq!(account.held_amount(), Amount::from_str(expected_held_amount).unwrap());
+        assert_eq!(account.total_amount(), Amount::from_str(expected_total_amount).unwrap());
+        assert_eq!(matches!(account, Account::Frozen(_)), expected_account_frozen);
+    }
+
+    assert_eq!(resulting_accounts.len(), 4);
+}
@@ -0,0 +1,252 @@
+mod reorder_buffer;
+pub mod prometheus_metrics;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::time::{Duration, Instant};
+
+use crate::domain::payments_engine::engine_policy::BackwardsTimestampsPolicy;
+use crate::domain::payments_engine::operation_request::OperationRequest;
+use crate::domain::payments_engine::{OperationValidationError, PaymentsEngine};
+use crate::domain::transaction_module::transaction::Transaction;
+use crate::use_case::operation_input::{report_rejection, RowRejection};
+use crate::use_case::process_from_csv_use_case::CsvOperationType;
+use crate::use_case::processing_session::reorder_buffer::ReorderBuffer;
+
+#[derive(Default, Clone, Copy)]
+pub struct OperationTypeStatistics {
+    pub accepted: u64,
+    pub rejected: u64
+}
+
+/*
+  Rows that couldn't even be deserialized have no operation type, so they are only counted as malformed.
+  A rejected operation can fail more than one validation, so the sum of the validation errors can be greater than
+  the number of rejected rows.
+  The duration goes from the creation of the session to its end, all the inputs included.
+*/
+#[derive(Default, Clone)]
+pub struct ProcessingStatistics {
+    pub rows_read: u64,
+    pub rows_accepted: u64,
+    pub rows_rejected: u64,
+    pub rows_malformed: u64,
+    pub per_operation_type: BTreeMap<CsvOperationType, OperationTypeStatistics>,
+    pub per_validation_error: BTreeMap<OperationValidationError, u64>,
+    pub invalid_requests: u64,
+    pub invariant_violations: u64,
+    pub duration: Duration
+}
+
+impl ProcessingStatistics {
+    fn record(&mut self, operation_type: Option<CsvOperationType>, outcome: &Result<(), RowRejection>) {
+        self.rows_read += 1;
+
+        let operation_type_statistics = operation_type.map(|operation_type| self.per_operation_type.entry(operation_type).or_default());
+        match outcome {
+            Ok(()) => {
+                self.rows_accepted += 1;
+                if let Some(operation_type_statistics) = operation_type_statistics {
+                    operation_type_statistics.accepted += 1;
+                }
+            }
+            Err(rejection) => {
+                self.rows_rejected += 1;
+                if let Some(operation_type_statistics) = operation_type_statistics {
+                    operation_type_statistics.rejected += 1;
+                }
+
+                match rejection {
+                    RowRejection::Malformed(_) => self.rows_malformed += 1,
+                    RowRejection::InvalidRequest(_, _) => self.invalid_requests += 1,
+                    RowRejection::InvalidOperation(errors) => {
+                        for error in errors {
+                            *self.per_validation_error.entry(error.clone()).or_default() += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Display for ProcessingStatistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "rows: {}, accepted: {}, rejected: {}", self.rows_read, self.rows_accepted, self.rows_rejected)?;
+
+        writeln!(f, "\n{:<50}{:>12}{:>12}", "operation type", "accepted", "rejected")?;
+        for (operation_type, operation_type_statistics) in &self.per_operation_type {
+            writeln!(f, "{:<50}{:>12}{:>12}", operation_type.to_string(), operation_type_statistics.accepted, operation_type_statistics.rejected)?;
+        }
+        writeln!(f, "{:<50}{:>12}{:>12}", "malformed", 0, self.rows_malformed)?;
+
+        writeln!(f, "\n{:<50}{:>12}", "rejection reason", "count")?;
+        writeln!(f, "{:<50}{:>12}", "Malformed", self.rows_malformed)?;
+        writeln!(f, "{:<50}{:>12}", "InvalidRequest", self.invalid_requests)?;
+        for (validation_error, count) in &self.per_validation_error {
+            writeln!(f, "{:<50}{:>12}", validation_error.to_string(), count)?;
+        }
+
+        if self.invariant_violations > 0 {
+            writeln!(f, "\ninvariant violations: {}", self.invariant_violations)?;
+        }
+        Ok(())
+    }
+}
+
+/*
+  A CSV file listing every skipped row with the reason why, so that it can be sent back to whoever produced the input.
+*/
+pub struct RejectionReport {
+    csv_writer: csv::Writer<File>
+}
+
+impl RejectionReport {
+    pub fn create(file_path: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut csv_writer = csv::Writer::from_path(file_path)?;
+        csv_writer.write_record(["source", "line", "reason"])?;
+        Ok(RejectionReport { csv_writer })
+    }
+
+    fn record(&mut self, source_name: &str, line: u64, rejection: &RowRejection) -> Result<(), csv::Error> {
+        self.csv_writer.write_record([source_name, &line.to_string(), &rejection.to_string()])
+    }
+}
+
+/*
+  Everything that lives across all the inputs of a single run: the engine itself, the statistics about the rows
+  and, optionally, where to report the rejected rows and after how many rows to stop.
+  It's passed from one input to the next one, exactly like the engine.
+  With the "reorder" policy for backwards timestamps, rows go through a reorder buffer before reaching the engine.
+*/
+pub struct ProcessingSession {
+    pub payments_engine: PaymentsEngine,
+    pub statistics: ProcessingStatistics,
+    rows_received: u64,
+    row_limit: Option<u64>,
+    rejection_report: Option<RejectionReport>,
+    reorder_buffer: Option<ReorderBuffer>,
+    started_at: Instant
+}
+
+impl ProcessingSession {
+    pub fn new(payments_engine: PaymentsEngine) -> Self {
+        let engine_policy = payments_engine.policy();
+        let reorder_buffer = match engine_policy.backwards_timestamps {
+            BackwardsTimestampsPolicy::Reorder => Some(ReorderBuffer::new(engine_policy.reorder_window)),
+            BackwardsTimestampsPolicy::Reject | BackwardsTimestampsPolicy::Warn => None
+        };
+
+        ProcessingSession {
+            payments_engine,
+            statistics: ProcessingStatistics::default(),
+            rows_received: 0,
+            row_limit: None,
+            rejection_report: None,
+            reorder_buffer,
+            started_at: Instant::now()
+        }
+    }
+
+    pub fn with_row_limit(self, row_limit: u64) -> Self {
+        ProcessingSession { row_limit: Some(row_limit), ..self }
+    }
+
+    pub fn with_rejection_report(self, rejection_report: RejectionReport) -> Self {
+        ProcessingSession { rejection_report: Some(rejection_report), ..self }
+    }
+
+    pub fn is_row_limit_reached(&self) -> bool {
+        self.row_limit.is_some_and(|row_limit| self.rows_received >= row_limit)
+    }
+
+    /*
+      Processes a single row against the engine, keeping track of the outcome, and returns the outcome of the row.
+      With a reorder buffer, the processed row may be an earlier one that was held back: then the row is held back in turn,
+      there's no outcome to return yet, and it will only be logged (and reported) when the row is processed.
+    */
+    pub fn process_row(&mut self, source_name: &str, line: u64, operation_request_result: Result<OperationRequest, RowRejection>) -> Option<Result<(), RowRejection>> {
+        self.rows_received += 1;
+
+        match self.reorder_buffer.as_mut() {
+            Some(reorder_buffer) => {
+                let buffered_row = reorder_buffer.push(source_name, line, operation_request_result)?;
+                let outcome = self.process_row_now(&buffered_row.source_name, buffered_row.line, buffered_row.operation_request_result);
+                (buffered_row.source_name == source_name && buffered_row.line == line).then_some(outcome)
+            }
+            None => Some(self.process_row_now(source_name, line, operation_request_result))
+        }
+    }
+
+    /*
+      The client and the transaction are kept for the rejection log: the client of a dispute, a resolve or a chargeback is the one of the transaction it references,
+      only looked up when it's rejected.
+    */
+    fn process_row_now(&mut self, source_name: &str, line: u64, operation_request_result: Result<OperationRequest, RowRejection>) -> Result<(), RowRejection> {
+        let _row_span_guard = tracing::debug_span!("row", source = source_name, line).entered();
+
+        let operation_type = match &operation_request_result {
+            Ok(operation_request) => Some(CsvOperationType::of(operation_request)),
+            Err(RowRejection::InvalidRequest(operation_type, _)) => Some(*operation_type),
+            Err(_) => None
+        };
+        let maybe_tx = operation_request_result.as_ref().ok().map(OperationRequest::transaction_id);
+        let maybe_client = match &operation_request_result {
+            Ok(OperationRequest::Deposit(deposit_operation_request)) => Some(deposit_operation_request.account_id),
+            Ok(OperationRequest::Withdrawal(withdrawal_operation_request)) => Some(withdrawal_operation_request.account_id),
+            _ => None
+        };
+
+        let outcome = operation_request_result
+            .and_then(|operation_request| self.payments_engine.process(operation_request).map_err(RowRejection::InvalidOperation));
+
+        self.statistics.record(operation_type, &outcome);
+
+        for invariant_violation in self.payments_engine.take_invariant_violations() {
+            tracing::error!(source = source_name, line, %invariant_violation, "Row broke an engine invariant");
+            self.statistics.invariant_violations += 1;
+        }
+
+        if let Err(rejection) = &outcome {
+            let maybe_client = maybe_client.or_else(|| maybe_tx.and_then(|tx| self.payments_engine.transaction(tx)).map(Transaction::account_id));
+            report_rejection(source_name, line, operation_type, maybe_client, maybe_tx, rejection);
+
+            if let Some(rejection_report) = self.rejection_report.as_mut()
+                && let Err(error) = rejection_report.record(source_name, line, rejection) {
+                tracing::error!(%error, "Error while writing the rejection report");
+            }
+        }
+        outcome
+    }
+
+    /*
+      Processes the rows still held back by the reorder buffer, flushes the rejection report, if any, and hands back the engine and the statistics.
+    */
+    pub fn finish(mut self) -> Result<(PaymentsEngine, ProcessingStatistics), Box<dyn Error + Send + Sync>> {
+        while let Some(buffered_row) = self.reorder_buffer.as_mut().and_then(ReorderBuffer::pop) {
+            // Already logged and reported, like every outcome
+            let _ = self.process_row_now(&buffered_row.source_name, buffered_row.line, buffered_row.operation_request_result);
+        }
+
+        if let Some(mut rejection_report) = self.rejection_report {
+            rejection_report.csv_writer.flush()?;
+        }
+        self.statistics.duration = self.started_at.elapsed();
+        Ok((self.payments_engine, self.statistics))
+    }
+}
+
+/*
+  Processes a whole CSV fixture through a session, for the tests of whatever reads the engine or the statistics afterwards.
+*/
+#[cfg(test)]
+pub(crate) async fn process_fixture(fixture_path: &str, payments_engine: PaymentsEngine) -> (PaymentsEngine, ProcessingStatistics) {
+    let processing_session = ProcessingSession::new(payments_engine);
+    let result = crate::use_case::process_from_csv_use_case::process_from_csv(crate::use_case::operation_input::InputSource::File(fixture_path.to_string()), processing_session).await.await.unwrap().unwrap();
+    result.finish().unwrap()
+}
+
+#[cfg(test)]
+mod processing_session_test;
@@ -0,0 +1,256 @@
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::fmt::format::FmtSpan;
+use crate::domain::Amount;
+use crate::domain::account_module::account::Account;
+use crate::domain::account_module::account_event::AccountEventKind;
+use crate::domain::payments_engine::engine_policy::{BackwardsTimestampsPolicy, EnginePolicy, TimeLimit};
+use crate::domain::payments_engine::{OperationValidationError, PaymentsEngine};
+use crate::domain::payments_engine::operation_request::{DepositOperationRequest, DisputeOperationRequest, OperationRequest, WithdrawalOperationRequest};
+use crate::use_case::operation_input::RowRejection;
+use crate::use_case::process_from_csv_use_case::CsvOperationType;
+use crate::use_case::processing_session::{process_fixture, ProcessingSession, ProcessingStatistics};
+
+#[tokio::test]
+async fn test_statistics_against_comprehensive_fixture() {
+    let (_, statistics) = process_fixture("fixtures/comprehensive_test_with_errors.csv", PaymentsEngine::new()).await;
+
+    assert_eq!(statistics.rows_read, 30);
+    assert_eq!(statistics.rows_accepted, 18);
+    assert_eq!(statistics.rows_rejected, 12);
+    assert_eq!(statistics.rows_malformed, 2);
+    assert_eq!(statistics.invalid_requests, 1);
+
+    let expected_per_operation_type = [
+        (CsvOperationType::Deposit,    10, 2),
+        (CsvOperationType::Withdrawal, 1,  4),
+        (CsvOperationType::Dispute,    4,  2),
+        (CsvOperationType::Resolve,    2,  1),
+        (CsvOperationType::Chargeback, 1,  1),
+    ];
+    for (operation_type, expected_accepted, expected_rejected) in expected_per_operation_type {
+        let operation_type_statistics = statistics.per_operation_type[&operation_type];
+        assert_eq!(operation_type_statistics.accepted, expected_accepted);
+        assert_eq!(operation_type_statistics.rejected, expected_rejected);
+    }
+
+    let expected_per_validation_error = [
+        (OperationValidationError::AccountNotFound,                           1),
+        (OperationValidationError::AccountFrozen,                             2),
+        (OperationValidationError::NegativeAmount,                            1),
+        (OperationValidationError::NotEnoughAvailableAmount,                  1),
+        (OperationValidationError::TransactionIdAlreadyExisting,              1),
+        (OperationValidationError::TransactionNotFound,                       1),
+        (OperationValidationError::ReferencedTransactionIsNotDeposit,         1),
+        (OperationValidationError::ReferencedTransactionIsNotDisputedDeposit, 2),
+    ];
+    for (validation_error, expected_count) in &expected_per_validation_error {
+        assert_eq!(statistics.per_validation_error[validation_error], *expected_count);
+    }
+    assert_eq!(statistics.per_validation_error.len(), expected_per_validation_error.len());
+}
+
+async fn process_timestamps_fixture(engine_policy: EnginePolicy) -> (PaymentsEngine, ProcessingStatistics) {
+    process_fixture("fixtures/timestamps_out_of_order.csv", PaymentsEngine::with_policy(engine_policy)).await
+}
+
+#[tokio::test]
+async fn test_backwards_timestamps_are_rejected_with_the_reject_policy() {
+    let (payments_engine, statistics) = process_timestamps_fixture(EnginePolicy { backwards_timestamps: BackwardsTimestampsPolicy::Reject, ..EnginePolicy::default() }).await;
+
+    assert_eq!(statistics.rows_accepted, 3);
+    assert_eq!(statistics.per_validation_error[&OperationValidationError::TimestampOutOfOrder], 2);
+    assert_eq!(payments_engine.account(1).unwrap().available_amount(), Amount::new(15, 0));
+    assert_eq!(payments_engine.transaction(2).unwrap().timestamp(), Some("2024-03-01T10:02:00Z".parse().unwrap()));
+    assert_eq!(payments_engine.transaction(5).unwrap().timestamp(), None);
+}
+
+#[tokio::test]
+async fn test_backwards_timestamps_are_processed_with_the_warn_policy() {
+    let (payments_engine, statistics) = process_timestamps_fixture(EnginePolicy::default()).await;
+
+    assert_eq!(statistics.rows_accepted, 5);
+    assert_eq!(payments_engine.account(1).unwrap().available_amount(), Amount::new(8, 0));
+}
+
+#[tokio::test]
+async fn test_backwards_timestamps_are_reordered_within_the_window() {
+    let (payments_engine, statistics) = process_timestamps_fixture(EnginePolicy { backwards_timestamps: BackwardsTimestampsPolicy::Reorder, reorder_window: 2, ..EnginePolicy::default() }).await;
+
+    // The withdrawal is moved before the second deposit, while the last deposit of client 1 is too late even for the window
+    assert_eq!(statistics.rows_accepted, 4);
+    assert_eq!(statistics.per_validation_error[&OperationValidationError::TimestampOutOfOrder], 1);
+    assert_eq!(payments_engine.account(1).unwrap().available_amount(), Amount::new(7, 0));
+    assert_eq!(payments_engine.account_history(1).iter().map(|account_event| account_event.transaction_id()).collect::<Vec<_>>(), vec![1, 3, 2]);
+}
+
+async fn process_dispute_windows_fixture(auto_resolve_expired_disputes: bool) -> (PaymentsEngine, ProcessingStatistics) {
+    let engine_policy = EnginePolicy {
+        dispute_window: TimeLimit { max_seconds: Some(30 * 24 * 3600), max_operations: None },
+        chargeback_deadline: TimeLimit { max_seconds: Some(7 * 24 * 3600), max_operations: Some(2) },
+        auto_resolve_expired_disputes,
+        ..EnginePolicy::default()
+    };
+    process_fixture("fixtures/dispute_windows.csv", PaymentsEngine::with_policy(engine_policy)).await
+}
+
+#[tokio::test]
+async fn test_disputes_and_chargebacks_past_their_deadlines_are_rejected() {
+    let (payments_engine, statistics) = process_dispute_windows_fixture(false).await;
+
+    assert_eq!(statistics.per_validation_error[&OperationValidationError::DisputeWindowExpired], 1);
+    assert_eq!(statistics.per_validation_error[&OperationValidationError::ChargebackDeadlineExpired], 1);
+    assert_eq!(payments_engine.account(1).unwrap().held_amount(), Amount::new(5, 0));
+    assert_eq!(payments_engine.account(2).unwrap().held_amount(), Amount::new(4, 0));
+    assert_eq!(payments_engine.account(3).unwrap().held_amount(), Amount::new(2, 0));
+}
+
+#[tokio::test]
+async fn test_disputes_left_open_past_the_chargeback_deadline_are_resolved() {
+    let (payments_engine, _) = process_dispute_windows_fixture(true).await;
+
+    // The rejected chargeback is the operation that moves the clock past every deadline, by time for clients 1 and 3 and by operations for client 2
+    for (account_id, expected_available_amount) in [(1, Amount::new(15, 0)), (2, Amount::new(4, 0)), (3, Amount::new(2, 0))] {
+        let account = payments_engine.account(account_id).unwrap();
+        assert_eq!(account.available_amount(), expected_available_amount);
+        assert_eq!(account.held_amount(), Amount::ZERO);
+        assert!(matches!(account, Account::Active(_)));
+
+        let last_account_event = payments_engine.account_history(account_id).last().unwrap();
+        assert_eq!(last_account_event.kind(), AccountEventKind::DisputeExpired);
+        assert_eq!(last_account_event.operation_index(), 9);
+    }
+}
+
+#[tokio::test]
+async fn test_disputes_are_resolved_in_the_order_they_expire() {
+    let engine_policy = EnginePolicy {
+        chargeback_deadline: TimeLimit { max_seconds: Some(3600), max_operations: Some(10) },
+        auto_resolve_expired_disputes: true,
+        ..EnginePolicy::default()
+    };
+    let (payments_engine, _) = process_fixture("fixtures/dispute_expiry_order.csv", PaymentsEngine::with_policy(engine_policy).with_invariant_checks()).await;
+
+    // The dispute without a timestamp expires by operations, the other ones by time: the one with the backwards timestamp
+    // is already past its deadline when it's opened, so it expires right away
+    assert_eq!(payments_engine.account(1).unwrap().held_amount(), Amount::new(10, 0));
+    for (account_id, expected_operation_index) in [(3, 6), (2, 8)] {
+        let account = payments_engine.account(account_id).unwrap();
+        assert_eq!(account.held_amount(), Amount::ZERO);
+
+        let last_account_event = payments_engine.account_history(account_id).last().unwrap();
+        assert_eq!(last_account_event.kind(), AccountEventKind::DisputeExpired);
+        assert_eq!(last_account_event.operation_index(), expected_operation_index);
+    }
+}
+
+#[tokio::test]
+async fn test_comprehensive_fixture_keeps_the_invariants_in_checked_mode() {
+    let (_, statistics) = process_fixture("fixtures/comprehensive_test_with_errors.csv", PaymentsEngine::new().with_invariant_checks()).await;
+
+    assert_eq!(statistics.rows_accepted, 18);
+    assert_eq!(statistics.invariant_violations, 0);
+}
+
+/*
+  Each fixture deposits twice half of the range of the amount, then withdraws 1: the fixture and the balance left depend on the amount type.
+*/
+#[cfg(not(feature = "fixed_point_amount"))]
+const BALANCE_OVERFLOW_FIXTURE: (&str, &str) = ("fixtures/balance_overflow.csv", "49999999999999999999999999999");
+#[cfg(feature = "fixed_point_amount")]
+const BALANCE_OVERFLOW_FIXTURE: (&str, &str) = ("fixtures/balance_overflow_fixed_point.csv", "499999999999999");
+
+async fn process_balance_overflow_fixture(max_balance: Option<Amount>) -> (PaymentsEngine, ProcessingStatistics) {
+    let engine_policy = EnginePolicy { max_balance, ..EnginePolicy::default() };
+    process_fixture(BALANCE_OVERFLOW_FIXTURE.0, PaymentsEngine::with_policy(engine_policy)).await
+}
+
+#[tokio::test]
+async fn test_deposits_overflowing_the_balance_are_rejected() {
+    let (payments_engine, statistics) = process_balance_overflow_fixture(None).await;
+
+    assert_eq!(statistics.rows_accepted, 4);
+    assert_eq!(statistics.per_validation_error[&OperationValidationError::BalanceOverflow], 1);
+    assert_eq!(payments_engine.account(1).unwrap().available_amount(), Amount::from_str(BALANCE_OVERFLOW_FIXTURE.1).unwrap());
+}
+
+#[tokio::test]
+async fn test_operations_past_the_max_balance_are_rejected() {
+    let (payments_engine, statistics) = process_balance_overflow_fixture(Some(Amount::new(1000, 0))).await;
+
+    assert_eq!(statistics.rows_accepted, 1);
+    assert_eq!(statistics.per_validation_error[&OperationValidationError::BalanceOverflow], 3);
+    assert!(payments_engine.account(1).is_none());
+    assert_eq!(payments_engine.account(2).unwrap().available_amount(), Amount::new(1000, 0));
+}
+
+#[derive(Clone, Default)]
+struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for LogBuffer {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/*
+  Rows processed with a JSON subscriber at the debug level: every line logged is a JSON object.
+*/
+fn process_rows_logging_json(rows: Vec<Result<OperationRequest, RowRejection>>) -> Vec<serde_json::Value> {
+    let log_buffer = LogBuffer::default();
+    let subscriber_log_buffer = log_buffer.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(move || subscriber_log_buffer.clone())
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let mut processing_session = ProcessingSession::new(PaymentsEngine::new());
+        for (index, row) in rows.into_iter().enumerate() {
+            processing_session.process_row("rows.csv", index as u64 + 2, row);
+        }
+    });
+
+    let logs = String::from_utf8(log_buffer.0.lock().unwrap().clone()).unwrap();
+    logs.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+}
+
+#[test]
+fn test_rows_are_logged_with_their_operation() {
+    let deposit = OperationRequest::Deposit(DepositOperationRequest { account_id: 1, transaction_id: 1, amount: Amount::new(10, 0), timestamp: None });
+    let dispute = OperationRequest::Dispute(DisputeOperationRequest { transaction_id: 1, timestamp: None });
+    let withdrawal = OperationRequest::Withdrawal(WithdrawalOperationRequest { account_id: 2, transaction_id: 2, amount: Amount::new(5, 0), timestamp: None });
+    let logs = process_rows_logging_json(vec![Ok(deposit), Ok(dispute.clone()), Ok(withdrawal), Ok(dispute), Err(RowRejection::Malformed("invalid digit".to_string()))]);
+
+    let operation_spans: Vec<&serde_json::Value> = logs.iter()
+        .filter(|log| log["fields"]["message"] == "close" && log["span"]["name"] == "process_operation")
+        .map(|log| &log["span"])
+        .collect();
+    assert_eq!(operation_spans.len(), 4);
+    assert_eq!(operation_spans[0]["operation_type"], "deposit");
+    assert_eq!(operation_spans[0]["outcome"], "accepted");
+    // The client of a dispute is the one of the deposit it references
+    assert_eq!(operation_spans[1]["client"], 1);
+    assert_eq!(operation_spans[1]["tx"], 1);
+    assert_eq!(operation_spans[2]["outcome"], "rejected");
+    assert_eq!(operation_spans[2]["errors"], "[AccountNotFound]");
+
+    let rejections: Vec<&serde_json::Value> = logs.iter().filter(|log| log["fields"]["message"] == "Row skipped").collect();
+    assert_eq!(rejections.len(), 3);
+    assert_eq!(rejections[0]["fields"]["line"], 4);
+    assert_eq!(rejections[0]["fields"]["client"], 2);
+    assert_eq!(rejections[0]["fields"]["operation_type"], "withdrawal");
+    assert_eq!(rejections[1]["fields"]["line"], 5);
+    assert_eq!(rejections[1]["fields"]["client"], 1);
+    assert_eq!(rejections[1]["fields"]["tx"], 1);
+    assert_eq!(rejections[1]["span"]["name"], "row");
+    assert_eq!(rejections[2]["fields"]["line"], 6);
+    assert!(rejections[2]["fields"].get("client").is_none());
+}
@@ -0,0 +1,67 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::domain::payments_engine::engine_metrics::EngineMetrics;
+use crate::use_case::processing_session::ProcessingStatistics;
+
+/*
+  The metrics of a whole run in the Prometheus text exposition format, so that a node exporter (textfile collector)
+  or a push to a gateway can pick them up after a batch. The rows and the validation errors come from the statistics of the session,
+  the processing latency from the engine, when its metrics are on.
+*/
+pub fn write_prometheus_metrics(output: &mut impl Write, statistics: &ProcessingStatistics, maybe_engine_metrics: Option<&EngineMetrics>) -> std::io::Result<()> {
+    write_metric_header(output, "payments_rows_total", "counter", "Rows read from the inputs, by outcome")?;
+    writeln!(output, "payments_rows_total{{outcome=\"accepted\"}} {}", statistics.rows_accepted)?;
+    writeln!(output, "payments_rows_total{{outcome=\"rejected\"}} {}", statistics.rows_rejected)?;
+
+    write_metric_header(output, "payments_rows_malformed_total", "counter", "Rows that couldn't be deserialized")?;
+    writeln!(output, "payments_rows_malformed_total {}", statistics.rows_malformed)?;
+
+    write_metric_header(output, "payments_invalid_requests_total", "counter", "Rows that couldn't be converted to an operation request")?;
+    writeln!(output, "payments_invalid_requests_total {}", statistics.invalid_requests)?;
+
+    write_metric_header(output, "payments_operations_total", "counter", "Rows by operation type and outcome")?;
+    for (operation_type, operation_type_statistics) in &statistics.per_operation_type {
+        writeln!(output, "payments_operations_total{{type=\"{operation_type}\",outcome=\"accepted\"}} {}", operation_type_statistics.accepted)?;
+        writeln!(output, "payments_operations_total{{type=\"{operation_type}\",outcome=\"rejected\"}} {}", operation_type_statistics.rejected)?;
+    }
+
+    write_metric_header(output, "payments_validation_errors_total", "counter", "Validation errors of the rejected operations: an operation can fail more than one validation")?;
+    for (validation_error, count) in &statistics.per_validation_error {
+        writeln!(output, "payments_validation_errors_total{{error=\"{validation_error}\"}} {count}")?;
+    }
+
+    write_metric_header(output, "payments_invariant_violations_total", "counter", "Engine invariants broken in checked mode")?;
+    writeln!(output, "payments_invariant_violations_total {}", statistics.invariant_violations)?;
+
+    write_metric_header(output, "payments_run_duration_seconds", "gauge", "How long the run took, all the inputs included")?;
+    writeln!(output, "payments_run_duration_seconds {}", statistics.duration.as_secs_f64())?;
+
+    if let Some(engine_metrics) = maybe_engine_metrics {
+        let processing_latency = &engine_metrics.processing_latency;
+        write_metric_header(output, "payments_operation_processing_seconds", "histogram", "Time the engine took to validate and apply an operation")?;
+        for (maybe_bucket_bound, cumulative_count) in processing_latency.cumulative_buckets() {
+            let bucket_bound = maybe_bucket_bound.map_or_else(|| "+Inf".to_owned(), |bucket_bound| bucket_bound.as_secs_f64().to_string());
+            writeln!(output, "payments_operation_processing_seconds_bucket{{le=\"{bucket_bound}\"}} {cumulative_count}")?;
+        }
+        writeln!(output, "payments_operation_processing_seconds_sum {}", processing_latency.sum().as_secs_f64())?;
+        writeln!(output, "payments_operation_processing_seconds_count {}", processing_latency.count())?;
+    }
+    Ok(())
+}
+
+pub fn write_prometheus_metrics_file(file_path: &str, statistics: &ProcessingStatistics, maybe_engine_metrics: Option<&EngineMetrics>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut output = BufWriter::new(File::create(file_path)?);
+    write_prometheus_metrics(&mut output, statistics, maybe_engine_metrics)?;
+    output.flush()?;
+    Ok(())
+}
+
+fn write_metric_header(output: &mut impl Write, name: &str, metric_type: &str, help: &str) -> std::io::Result<()> {
+    writeln!(output, "# HELP {name} {help}")?;
+    writeln!(output, "# TYPE {name} {metric_type}")
+}
+
+#[cfg(test)]
+mod prometheus_metrics_test;
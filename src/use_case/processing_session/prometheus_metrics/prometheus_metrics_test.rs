@@ -0,0 +1,58 @@
+use crate::domain::payments_engine::PaymentsEngine;
+use crate::use_case::processing_session::process_fixture;
+use crate::use_case::processing_session::prometheus_metrics::write_prometheus_metrics;
+
+async fn comprehensive_fixture_metrics(payments_engine: PaymentsEngine) -> String {
+    let (payments_engine, statistics) = process_fixture("fixtures/comprehensive_test_with_errors.csv", payments_engine).await;
+
+    let mut output = vec![];
+    write_prometheus_metrics(&mut output, &statistics, payments_engine.metrics()).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[tokio::test]
+async fn test_metrics_of_the_comprehensive_fixture() {
+    let metrics = comprehensive_fixture_metrics(PaymentsEngine::new().with_metrics()).await;
+    let lines: Vec<&str> = metrics.lines().collect();
+
+    let expected_lines = [
+        "# TYPE payments_rows_total counter",
+        "payments_rows_total{outcome=\"accepted\"} 18",
+        "payments_rows_total{outcome=\"rejected\"} 12",
+        "payments_rows_malformed_total 2",
+        "payments_invalid_requests_total 1",
+        "payments_operations_total{type=\"deposit\",outcome=\"accepted\"} 10",
+        "payments_operations_total{type=\"deposit\",outcome=\"rejected\"} 2",
+        "payments_operations_total{type=\"withdrawal\",outcome=\"rejected\"} 4",
+        "payments_operations_total{type=\"chargeback\",outcome=\"accepted\"} 1",
+        "payments_validation_errors_total{error=\"AccountFrozen\"} 2",
+        "payments_validation_errors_total{error=\"ReferencedTransactionIsNotDisputedDeposit\"} 2",
+        "payments_invariant_violations_total 0",
+        "# TYPE payments_run_duration_seconds gauge",
+        "# TYPE payments_operation_processing_seconds histogram",
+        "payments_operation_processing_seconds_bucket{le=\"+Inf\"} 27",
+        "payments_operation_processing_seconds_count 27",
+    ];
+    for expected_line in expected_lines {
+        assert!(lines.contains(&expected_line), "missing line: {expected_line}\n{metrics}");
+    }
+
+    /*
+      Malformed rows and invalid requests never reach the engine: the other 27 rows all have an observed latency, in cumulative buckets.
+    */
+    let bucket_counts: Vec<u64> = lines.iter()
+        .filter(|line| line.starts_with("payments_operation_processing_seconds_bucket"))
+        .map(|line| line.rsplit(' ').next().unwrap().parse().unwrap())
+        .collect();
+    assert_eq!(bucket_counts.len(), 11);
+    assert!(bucket_counts.is_sorted());
+}
+
+#[tokio::test]
+async fn test_engine_metrics_are_left_out_when_off() {
+    let metrics = comprehensive_fixture_metrics(PaymentsEngine::new()).await;
+
+    assert!(metrics.contains("payments_rows_total{outcome=\"accepted\"} 18"));
+    assert!(metrics.contains("payments_validation_errors_total{error=\"AccountFrozen\"} 2"));
+    assert!(!metrics.contains("payments_operation_processing_seconds"));
+}
@@ -0,0 +1,78 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use crate::domain::Timestamp;
+use crate::domain::payments_engine::operation_request::OperationRequest;
+use crate::use_case::operation_input::RowRejection;
+
+pub struct BufferedRow {
+    pub source_name: String,
+    pub line: u64,
+    pub operation_request_result: Result<OperationRequest, RowRejection>,
+    ordering_key: (Option<Timestamp>, u64)
+}
+
+impl PartialEq for BufferedRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.ordering_key == other.ordering_key
+    }
+}
+
+impl Eq for BufferedRow {}
+
+impl PartialOrd for BufferedRow {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BufferedRow {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ordering_key.cmp(&other.ordering_key)
+    }
+}
+
+/*
+  Holds back up to "window" rows and releases them sorted by timestamp, so that rows slightly out of order
+  are processed in the right one. Ties, and rows without a timestamp, keep the order they arrived in:
+  a row without a timestamp is sorted as if it had the latest timestamp seen so far.
+  A row arriving later than the window allows is released anyway, and the engine rejects it with TimestampOutOfOrder.
+*/
+pub struct ReorderBuffer {
+    window: usize,
+    rows: BinaryHeap<Reverse<BufferedRow>>,
+    rows_received: u64,
+    latest_timestamp: Option<Timestamp>
+}
+
+impl ReorderBuffer {
+    pub fn new(window: usize) -> Self {
+        ReorderBuffer { window, rows: BinaryHeap::new(), rows_received: 0, latest_timestamp: None }
+    }
+
+    /*
+      Buffers the row and returns the one that has to be processed now, if the window is full.
+    */
+    pub fn push(&mut self, source_name: &str, line: u64, operation_request_result: Result<OperationRequest, RowRejection>) -> Option<BufferedRow> {
+        let maybe_timestamp = operation_request_result.as_ref().ok().and_then(OperationRequest::timestamp);
+        self.latest_timestamp = self.latest_timestamp.max(maybe_timestamp);
+        self.rows_received += 1;
+
+        self.rows.push(Reverse(BufferedRow {
+            source_name: source_name.to_owned(),
+            line,
+            operation_request_result,
+            ordering_key: (maybe_timestamp.or(self.latest_timestamp), self.rows_received)
+        }));
+
+        if self.rows.len() > self.window {
+            self.pop()
+        } else {
+            None
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<BufferedRow> {
+        self.rows.pop().map(|Reverse(buffered_row)| buffered_row)
+    }
+}
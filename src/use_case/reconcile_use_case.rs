@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer};
+
+use crate::domain::account_module::account::AccountId;
+use crate::domain::Amount;
+use crate::domain::payments_engine::PaymentsEngine;
+use crate::output_printer::{print_to_output, with_output_scale, AccountOutputRow, OutputSettings};
+
+/*
+  A row of the expected balances file, which has the same shape as the CSV output of the engine.
+  Amounts are read as text: the CSV reader would otherwise hand them over as a float, rounding any balance past 15 significant digits.
+*/
+#[derive(Deserialize)]
+pub struct ExpectedAccountRow {
+    pub client: AccountId,
+    #[serde(deserialize_with = "deserialize_exact_amount")]
+    pub available: Amount,
+    #[serde(deserialize_with = "deserialize_exact_amount")]
+    pub held: Amount,
+    #[serde(deserialize_with = "deserialize_exact_amount")]
+    pub total: Amount,
+    pub locked: bool
+}
+
+fn deserialize_exact_amount<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Amount, D::Error> {
+    let amount = String::deserialize(deserializer)?;
+    Amount::from_str(&amount).map_err(|error| serde::de::Error::custom(format!("Invalid amount {amount}: {error}")))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BalanceDifferenceKind {
+    // Expected, but the engine doesn't know the account
+    Missing,
+    // Known by the engine, but not expected
+    Extra,
+    // Expected more than once: only the first row is compared
+    Duplicate,
+    Available,
+    Held,
+    Total,
+    Locked
+}
+
+impl Display for BalanceDifferenceKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let difference_kind = match self {
+            BalanceDifferenceKind::Missing => "missing",
+            BalanceDifferenceKind::Extra => "extra",
+            BalanceDifferenceKind::Duplicate => "duplicate",
+            BalanceDifferenceKind::Available => "available",
+            BalanceDifferenceKind::Held => "held",
+            BalanceDifferenceKind::Total => "total",
+            BalanceDifferenceKind::Locked => "locked"
+        };
+        write!(f, "{difference_kind}")
+    }
+}
+
+/*
+  Expected and actual values are kept as they would be printed, so that the report can be read next to both files.
+  A missing account has no actual value, an extra one has no expected value, a duplicate one has neither.
+*/
+#[derive(PartialEq, Eq, Debug)]
+pub struct BalanceDifference {
+    pub client: AccountId,
+    pub kind: BalanceDifferenceKind,
+    pub expected: Option<String>,
+    pub actual: Option<String>
+}
+
+pub fn read_expected_balances(reader: impl Read) -> Result<Vec<ExpectedAccountRow>, Box<dyn Error + Send + Sync>> {
+    let expected_account_rows = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader)
+        .into_deserialize::<ExpectedAccountRow>()
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(expected_account_rows)
+}
+
+/*
+  Compares the expected balances with the accounts of the engine. Amounts are compared by value, so 1.5 matches 1.5000.
+  Differences are sorted by client, and for each client by column. A client expected more than once is compared with its first row,
+  and every other row is reported as a duplicate.
+*/
+pub fn reconcile(payments_engine: &PaymentsEngine, expected_account_rows: &[ExpectedAccountRow]) -> Vec<BalanceDifference> {
+    let mut actual_account_rows: BTreeMap<AccountId, AccountOutputRow> = payments_engine.accounts()
+        .map(|account| (account.id(), AccountOutputRow::from_account(account)))
+        .collect();
+    let mut expected_clients = BTreeSet::new();
+    let mut balance_differences = vec![];
+
+    for expected_account_row in expected_account_rows {
+        if !expected_clients.insert(expected_account_row.client) {
+            balance_differences.push(BalanceDifference {
+                client: expected_account_row.client,
+                kind: BalanceDifferenceKind::Duplicate,
+                expected: None,
+                actual: None
+            });
+            continue;
+        }
+
+        match actual_account_rows.remove(&expected_account_row.client) {
+            Some(actual_account_row) => balance_differences.extend(compare(expected_account_row, &actual_account_row)),
+            None => balance_differences.push(BalanceDifference {
+                client: expected_account_row.client,
+                kind: BalanceDifferenceKind::Missing,
+                expected: None,
+                actual: None
+            })
+        }
+    }
+
+    balance_differences.extend(actual_account_rows.into_keys().map(|client| BalanceDifference {
+        client,
+        kind: BalanceDifferenceKind::Extra,
+        expected: None,
+        actual: None
+    }));
+
+    balance_differences.sort_by_key(|balance_difference| balance_difference.client);
+    balance_differences
+}
+
+fn compare(expected_account_row: &ExpectedAccountRow, actual_account_row: &AccountOutputRow) -> Vec<BalanceDifference> {
+    let amount_columns = [
+        (BalanceDifferenceKind::Available, expected_account_row.available, actual_account_row.available),
+        (BalanceDifferenceKind::Held, expected_account_row.held, actual_account_row.held),
+        (BalanceDifferenceKind::Total, expected_account_row.total, actual_account_row.total),
+    ];
+
+    let mut balance_differences: Vec<BalanceDifference> = amount_columns.into_iter()
+        .filter(|(_, expected_amount, actual_amount)| expected_amount != actual_amount)
+        .map(|(kind, expected_amount, actual_amount)| BalanceDifference {
+            client: actual_account_row.client,
+            kind,
+            expected: Some(with_output_scale(expected_amount).to_string()),
+            actual: Some(actual_amount.to_string())
+        })
+        .collect();
+
+    if expected_account_row.locked != actual_account_row.locked {
+        balance_differences.push(BalanceDifference {
+            client: actual_account_row.client,
+            kind: BalanceDifferenceKind::Locked,
+            expected: Some(expected_account_row.locked.to_string()),
+            actual: Some(actual_account_row.locked.to_string())
+        });
+    }
+    balance_differences
+}
+
+pub fn print_reconciliation_report(balance_differences: &[BalanceDifference], output_settings: &OutputSettings) {
+    print_to_output(output_settings, |output| write_reconciliation_report(output, balance_differences));
+}
+
+pub fn write_reconciliation_report(output: &mut (impl Write + ?Sized), balance_differences: &[BalanceDifference]) -> std::io::Result<()> {
+    writeln!(output, "client,difference,expected,actual")?;
+    for balance_difference in balance_differences {
+        writeln!(
+            output,
+            "{},{},{},{}",
+            balance_difference.client,
+            balance_difference.kind,
+            balance_difference.expected.as_deref().unwrap_or_default(),
+            balance_difference.actual.as_deref().unwrap_or_default()
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod reconcile_use_case_test;
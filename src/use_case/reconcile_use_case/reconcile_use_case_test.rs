@@ -0,0 +1,70 @@
+use crate::domain::Amount;
+use crate::domain::payments_engine::operation_request::{DepositOperationRequest, OperationRequest};
+use crate::domain::payments_engine::PaymentsEngine;
+use crate::use_case::processing_session::process_fixture;
+use crate::use_case::reconcile_use_case::{read_expected_balances, reconcile, write_reconciliation_report};
+
+async fn process_comprehensive_fixture() -> PaymentsEngine {
+    let (payments_engine, _) = process_fixture("fixtures/comprehensive_test_with_errors.csv", PaymentsEngine::new()).await;
+    payments_engine
+}
+
+#[tokio::test]
+async fn test_matching_balances_have_no_differences() {
+    let payments_engine = process_comprehensive_fixture().await;
+    let expected_balances = "client, available, held, total, locked
+        5, 123456789.1239, 0, 123456789.1239, false
+        1, -0.5, 0, -0.5, true
+        2, 2, 0, 2, false
+        3, 5, 0, 5, false
+        4, 1001, 12, 1013, false";
+
+    let balance_differences = reconcile(&payments_engine, &read_expected_balances(expected_balances.as_bytes()).unwrap());
+
+    assert!(balance_differences.is_empty());
+}
+
+#[tokio::test]
+async fn test_missing_extra_and_different_accounts_are_reported() {
+    let payments_engine = process_comprehensive_fixture().await;
+    let expected_balances = "client,available,held,total,locked
+        1,-0.5,0,-0.5,false
+        2,2,0,2,false
+        4,1000,12,1012,false
+        5,123456789.1239,0,123456789.1239,false
+        6,1,0,1,false";
+
+    let balance_differences = reconcile(&payments_engine, &read_expected_balances(expected_balances.as_bytes()).unwrap());
+
+    let mut output = vec![];
+    write_reconciliation_report(&mut output, &balance_differences).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "client,difference,expected,actual\n\
+        1,locked,false,true\n\
+        3,extra,,\n\
+        4,available,1000.0000,1001.0000\n\
+        4,total,1012.0000,1013.0000\n\
+        6,missing,,\n");
+}
+
+#[test]
+fn test_amounts_are_compared_with_every_digit_and_duplicate_clients_are_reported() {
+    let mut payments_engine = PaymentsEngine::new();
+    for (account_id, transaction_id) in [(1, 1), (2, 2)] {
+        let amount = "12345678901234.5678".parse::<Amount>().unwrap();
+        payments_engine.process(OperationRequest::Deposit(DepositOperationRequest { account_id, transaction_id, amount, timestamp: None })).unwrap();
+    }
+    // As floats, 12345678901234.5678 and 12345678901234.5679 are the same number
+    let expected_balances = "client,available,held,total,locked
+        1,12345678901234.5678,0,12345678901234.5678,false
+        2,12345678901234.5679,0,12345678901234.5679,false
+        1,0,0,0,false";
+
+    let balance_differences = reconcile(&payments_engine, &read_expected_balances(expected_balances.as_bytes()).unwrap());
+
+    let mut output = vec![];
+    write_reconciliation_report(&mut output, &balance_differences).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "client,difference,expected,actual\n\
+        1,duplicate,,\n\
+        2,available,12345678901234.5679,12345678901234.5678\n\
+        2,total,12345678901234.5679,12345678901234.5678\n");
+}
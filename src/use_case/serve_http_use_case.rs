@@ -0,0 +1,207 @@
+use std::error::Error;
+use std::future::Future;
+use std::sync::Arc;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tracing::info;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::domain::account_module::account::AccountId;
+use crate::domain::{Amount, Timestamp};
+use crate::domain::transaction_module::transaction::{Transaction, TransactionId};
+use crate::output_printer::{with_output_scale, AccountEventOutputRow, AccountOutputRow};
+use crate::use_case::operation_input::RowRejection;
+use crate::use_case::process_from_jsonl_use_case::OperationJsonRow;
+use crate::use_case::processing_session::ProcessingSession;
+
+const HTTP_SOURCE_NAME: &str = "http";
+
+/*
+  The operations submitted over HTTP are a single source, "http", whose lines are the operations in the order they were received.
+*/
+struct HttpSession {
+    processing_session: ProcessingSession,
+    operations_received: u64
+}
+
+type SharedHttpSession = Arc<Mutex<HttpSession>>;
+
+/*
+  An operation has the same shape as a JSON Lines row: {"type": "deposit", "client": 1, "tx": 1, "amount": "1.5"}
+  Every operation is deserialized on its own, so a malformed one in a batch is rejected without failing the others.
+*/
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OperationsPayload {
+    Batch(Vec<Value>),
+    Single(Value)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum OperationStatus {
+    Accepted,
+    Rejected,
+    Pending
+}
+
+/*
+  A malformed operation has no tx when it isn't even readable.
+*/
+#[derive(Serialize)]
+struct OperationOutcomeJson {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tx: Option<TransactionId>,
+    status: OperationStatus,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<String>
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum TransactionTypeJson {
+    Deposit,
+    DisputedDeposit,
+    ChargedBackDeposit,
+    Withdrawal
+}
+
+#[derive(Serialize)]
+struct TransactionJson {
+    tx: TransactionId,
+    client: AccountId,
+    amount: Amount,
+    #[serde(rename = "type")]
+    transaction_type: TransactionTypeJson,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<Timestamp>
+}
+
+/*
+  HTTP mode: internal tools can submit operations and query the engine directly.
+  Operations go through the processing session like the rows of any other input, so they are counted, reported when rejected
+  and, with the "reorder" policy, held back by the reorder buffer: such an operation is "pending", and its outcome is only logged.
+  The session is shared between requests behind an async mutex: processing an operation is a short, synchronous, in-memory
+  piece of work, so requests are serialized on it exactly like the rows of a CSV.
+  On graceful shutdown (the shutdown future completing, Ctrl+C for the binary) the session is returned, still to be finished.
+*/
+pub async fn serve_http(listener: TcpListener, processing_session: ProcessingSession, shutdown: impl Future<Output = ()> + Send + 'static) -> Result<ProcessingSession, Box<dyn Error + Send + Sync>> {
+    let shared_http_session = Arc::new(Mutex::new(HttpSession { processing_session, operations_received: 0 }));
+    info!(address = %listener.local_addr()?, "Listening for HTTP requests");
+
+    axum::serve(listener, build_router(shared_http_session.clone()))
+        .with_graceful_shutdown(shutdown)
+        .await?;
+
+    let http_session = Arc::into_inner(shared_http_session)
+        .ok_or("The processing session is still shared after the HTTP server shutdown")?
+        .into_inner();
+
+    Ok(http_session.processing_session)
+}
+
+fn build_router(shared_http_session: SharedHttpSession) -> Router {
+    Router::new()
+        .route("/operations", post(submit_operations))
+        .route("/accounts", get(get_accounts))
+        .route("/accounts/{id}", get(get_account))
+        .route("/accounts/{id}/history", get(get_account_history))
+        .route("/transactions/{id}", get(get_transaction))
+        .with_state(shared_http_session)
+}
+
+async fn submit_operations(State(http_session): State<SharedHttpSession>, Json(payload): Json<OperationsPayload>) -> Response {
+    let mut http_session = http_session.lock().await;
+
+    match payload {
+        OperationsPayload::Single(operation_json) => {
+            let outcome = process_operation(&mut http_session, operation_json);
+            let status_code = match outcome.status {
+                OperationStatus::Accepted => StatusCode::OK,
+                OperationStatus::Rejected => StatusCode::UNPROCESSABLE_ENTITY,
+                OperationStatus::Pending => StatusCode::ACCEPTED
+            };
+            (status_code, Json(outcome)).into_response()
+        }
+        OperationsPayload::Batch(operations_json) => {
+            let outcomes: Vec<OperationOutcomeJson> = operations_json.into_iter()
+                .map(|operation_json| process_operation(&mut http_session, operation_json))
+                .collect();
+            Json(outcomes).into_response()
+        }
+    }
+}
+
+fn process_operation(http_session: &mut HttpSession, operation_json: Value) -> OperationOutcomeJson {
+    let tx = operation_json.get("tx").and_then(|tx| TransactionId::deserialize(tx).ok());
+    http_session.operations_received += 1;
+    let line = http_session.operations_received;
+
+    let operation_request_result = serde_json::from_value::<OperationJsonRow>(operation_json)
+        .map_err(|error| RowRejection::Malformed(error.to_string()))
+        .and_then(OperationJsonRow::into_operation_request);
+    match http_session.processing_session.process_row(HTTP_SOURCE_NAME, line, operation_request_result) {
+        Some(Ok(())) => OperationOutcomeJson { tx, status: OperationStatus::Accepted, errors: vec![] },
+        Some(Err(rejection)) => OperationOutcomeJson { tx, status: OperationStatus::Rejected, errors: rejection_errors(rejection) },
+        None => OperationOutcomeJson { tx, status: OperationStatus::Pending, errors: vec![] }
+    }
+}
+
+fn rejection_errors(rejection: RowRejection) -> Vec<String> {
+    match rejection {
+        RowRejection::Malformed(error_description) | RowRejection::InvalidRequest(_, error_description) => vec![error_description],
+        RowRejection::InvalidOperation(errors) => errors.iter().map(|error| error.to_string()).collect()
+    }
+}
+
+async fn get_accounts(State(http_session): State<SharedHttpSession>) -> Json<Vec<AccountOutputRow>> {
+    let payments_engine = &http_session.lock().await.processing_session.payments_engine;
+    Json(payments_engine.accounts().map(AccountOutputRow::from_account).collect())
+}
+
+async fn get_account(State(http_session): State<SharedHttpSession>, Path(account_id): Path<AccountId>) -> Result<Json<AccountOutputRow>, StatusCode> {
+    let payments_engine = &http_session.lock().await.processing_session.payments_engine;
+    payments_engine.account(account_id)
+        .map(|account| Json(AccountOutputRow::from_account(account)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn get_account_history(State(http_session): State<SharedHttpSession>, Path(account_id): Path<AccountId>) -> Result<Json<Vec<AccountEventOutputRow>>, StatusCode> {
+    let payments_engine = &http_session.lock().await.processing_session.payments_engine;
+    payments_engine.account(account_id)
+        .map(|_| Json(payments_engine.account_history(account_id).iter().map(AccountEventOutputRow::from_account_event).collect()))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn get_transaction(State(http_session): State<SharedHttpSession>, Path(transaction_id): Path<TransactionId>) -> Result<Json<TransactionJson>, StatusCode> {
+    let payments_engine = &http_session.lock().await.processing_session.payments_engine;
+    payments_engine.transaction(transaction_id)
+        .map(|transaction| Json(to_transaction_json(transaction)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+fn to_transaction_json(transaction: &Transaction) -> TransactionJson {
+    let transaction_type = match transaction {
+        Transaction::Deposit(_) => TransactionTypeJson::Deposit,
+        Transaction::DisputedDeposit(_) => TransactionTypeJson::DisputedDeposit,
+        Transaction::ChargedBackDeposit(_) => TransactionTypeJson::ChargedBackDeposit,
+        Transaction::Withdrawal(_) => TransactionTypeJson::Withdrawal
+    };
+
+    TransactionJson {
+        tx: transaction.id(),
+        client: transaction.account_id(),
+        amount: with_output_scale(transaction.amount()),
+        transaction_type,
+        timestamp: transaction.timestamp()
+    }
+}
+
+#[cfg(test)]
+mod serve_http_use_case_test;
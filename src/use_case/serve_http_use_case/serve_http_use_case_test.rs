@@ -0,0 +1,156 @@
+use std::sync::Arc;
+use axum::body::{to_bytes, Body};
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+use tower::ServiceExt;
+use crate::domain::payments_engine::engine_policy::{BackwardsTimestampsPolicy, EnginePolicy};
+use crate::domain::payments_engine::PaymentsEngine;
+use crate::use_case::processing_session::ProcessingSession;
+use crate::use_case::serve_http_use_case::{build_router, HttpSession, SharedHttpSession};
+
+fn new_shared_http_session(payments_engine: PaymentsEngine) -> SharedHttpSession {
+    Arc::new(Mutex::new(HttpSession { processing_session: ProcessingSession::new(payments_engine), operations_received: 0 }))
+}
+
+async fn send(router: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status_code = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json_body = if body.is_empty() { Value::Null } else { serde_json::from_slice(&body).unwrap() };
+    (status_code, json_body)
+}
+
+async fn post_operations(router: &Router, payload: Value) -> (StatusCode, Value) {
+    let request = Request::post("/operations").header("content-type", "application/json").body(Body::from(payload.to_string())).unwrap();
+    send(router, request).await
+}
+
+async fn get(router: &Router, uri: &str) -> (StatusCode, Value) {
+    send(router, Request::get(uri).body(Body::empty()).unwrap()).await
+}
+
+#[tokio::test]
+async fn test_single_operations_are_answered_with_their_outcome() {
+    let shared_http_session = new_shared_http_session(PaymentsEngine::new());
+    let router = build_router(shared_http_session.clone());
+
+    let (status_code, outcome) = post_operations(&router, json!({"type": "deposit", "client": 1, "tx": 1, "amount": "2.5"})).await;
+    assert_eq!(status_code, StatusCode::OK);
+    assert_eq!(outcome, json!({"tx": 1, "status": "accepted"}));
+
+    let (status_code, outcome) = post_operations(&router, json!({"type": "withdrawal", "client": 1, "tx": 2, "amount": "3"})).await;
+    assert_eq!(status_code, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(outcome, json!({"tx": 2, "status": "rejected", "errors": ["NotEnoughAvailableAmount"]}));
+
+    let (status_code, outcome) = post_operations(&router, json!({"type": "deposit", "client": 1, "tx": 3})).await;
+    assert_eq!(status_code, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(outcome["status"], "rejected");
+
+    let statistics = &shared_http_session.lock().await.processing_session.statistics;
+    assert_eq!(statistics.rows_accepted, 1);
+    assert_eq!(statistics.rows_rejected, 2);
+    assert_eq!(statistics.invalid_requests, 1);
+}
+
+#[tokio::test]
+async fn test_batches_are_answered_with_every_outcome_in_order() {
+    let router = build_router(new_shared_http_session(PaymentsEngine::new()));
+
+    let (status_code, outcomes) = post_operations(&router, json!([
+        {"type": "deposit", "client": 1, "tx": 1, "amount": "10"},
+        {"type": "deposit", "client": 1, "tx": 1, "amount": "10"},
+        {"type": "dispute", "client": 1, "tx": 1}
+    ])).await;
+
+    assert_eq!(status_code, StatusCode::OK);
+    assert_eq!(outcomes, json!([
+        {"tx": 1, "status": "accepted"},
+        {"tx": 1, "status": "rejected", "errors": ["TransactionIdAlreadyExisting"]},
+        {"tx": 1, "status": "accepted"}
+    ]));
+}
+
+#[tokio::test]
+async fn test_malformed_operations_in_a_batch_are_rejected_without_failing_the_others() {
+    let shared_http_session = new_shared_http_session(PaymentsEngine::new());
+    let router = build_router(shared_http_session.clone());
+
+    let (status_code, outcomes) = post_operations(&router, json!([
+        {"type": "deposit", "client": 1, "tx": 1, "amount": 1.5},
+        {"type": "deposit", "client": "one", "tx": 2, "amount": "1"},
+        "not an operation",
+        {"type": "deposit", "client": 1, "tx": 3, "amount": "2"}
+    ])).await;
+
+    assert_eq!(status_code, StatusCode::OK);
+    assert_eq!(outcomes[0], json!({"tx": 1, "status": "accepted"}));
+    assert_eq!(outcomes[1]["tx"], 2);
+    assert_eq!(outcomes[1]["status"], "rejected");
+    assert_eq!(outcomes[2]["tx"], Value::Null);
+    assert_eq!(outcomes[2]["status"], "rejected");
+    assert_eq!(outcomes[3], json!({"tx": 3, "status": "accepted"}));
+
+    let statistics = &shared_http_session.lock().await.processing_session.statistics;
+    assert_eq!(statistics.rows_accepted, 2);
+    assert_eq!(statistics.rows_malformed, 2);
+}
+
+#[tokio::test]
+async fn test_transaction_amounts_have_four_decimals() {
+    let router = build_router(new_shared_http_session(PaymentsEngine::new()));
+    post_operations(&router, json!({"type": "deposit", "client": 1, "tx": 1, "amount": "2.5"})).await;
+
+    let (_, transaction) = get(&router, "/transactions/1").await;
+    assert_eq!(transaction["amount"], "2.5000");
+}
+
+#[tokio::test]
+async fn test_operations_held_back_by_the_reorder_buffer_are_pending() {
+    let engine_policy = EnginePolicy { backwards_timestamps: BackwardsTimestampsPolicy::Reorder, reorder_window: 1, ..EnginePolicy::default() };
+    let router = build_router(new_shared_http_session(PaymentsEngine::with_policy(engine_policy)));
+
+    let (status_code, outcome) = post_operations(&router, json!({"type": "deposit", "client": 1, "tx": 1, "amount": "1", "timestamp": "2024-03-01T10:00:00Z"})).await;
+    assert_eq!(status_code, StatusCode::ACCEPTED);
+    assert_eq!(outcome, json!({"tx": 1, "status": "pending"}));
+
+    // The earlier one is released: it's processed, but this one is held back in turn
+    let (status_code, _) = post_operations(&router, json!({"type": "deposit", "client": 1, "tx": 2, "amount": "1", "timestamp": "2024-03-01T10:01:00Z"})).await;
+    assert_eq!(status_code, StatusCode::ACCEPTED);
+    assert_eq!(get(&router, "/transactions/1").await.0, StatusCode::OK);
+    assert_eq!(get(&router, "/transactions/2").await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_accounts_and_transactions_can_be_queried() {
+    let router = build_router(new_shared_http_session(PaymentsEngine::new()));
+    post_operations(&router, json!([
+        {"type": "deposit", "client": 2, "tx": 1, "amount": "10"},
+        {"type": "deposit", "client": 1, "tx": 2, "amount": "4"},
+        {"type": "dispute", "client": 2, "tx": 1}
+    ])).await;
+
+    let (status_code, accounts) = get(&router, "/accounts").await;
+    assert_eq!(status_code, StatusCode::OK);
+    let mut account_ids: Vec<u64> = accounts.as_array().unwrap().iter().map(|account| account["client"].as_u64().unwrap()).collect();
+    account_ids.sort();
+    assert_eq!(account_ids, vec![1, 2]);
+
+    let (status_code, account) = get(&router, "/accounts/2").await;
+    assert_eq!(status_code, StatusCode::OK);
+    assert_eq!(account["client"], 2);
+    assert_eq!(account["locked"], false);
+    assert_eq!(account["held"].as_str().unwrap().parse::<f64>().unwrap(), 10.0);
+    assert_eq!(get(&router, "/accounts/3").await.0, StatusCode::NOT_FOUND);
+
+    let (status_code, transaction) = get(&router, "/transactions/1").await;
+    assert_eq!(status_code, StatusCode::OK);
+    assert_eq!(transaction["client"], 2);
+    assert_eq!(transaction["type"], "disputed_deposit");
+    assert_eq!(get(&router, "/transactions/3").await.0, StatusCode::NOT_FOUND);
+
+    let (status_code, account_history) = get(&router, "/accounts/2/history").await;
+    assert_eq!(status_code, StatusCode::OK);
+    assert_eq!(account_history.as_array().unwrap().len(), 2);
+}
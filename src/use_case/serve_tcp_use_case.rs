@@ -0,0 +1,124 @@
+use std::error::Error;
+use std::future::Future;
+use futures::StreamExt;
+use tracing::{error, info};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::task::JoinSet;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tokio_util::sync::CancellationToken;
+
+use crate::domain::payments_engine::operation_request::OperationRequest;
+use crate::output_printer::{print_outcome, OutputSettings};
+use crate::use_case::operation_input::RowRejection;
+use crate::use_case::processing_session::ProcessingSession;
+use crate::use_case::process_from_csv_use_case::read_operation_requests_from_csv;
+
+const ENGINE_COMMANDS_BUFFER_SIZE: usize = 1024;
+
+enum EngineCommand {
+    Process { operation_request_result: Result<OperationRequest, RowRejection>, source_name: String, line: u64 },
+    Dump
+}
+
+/*
+  Long-lived mode: every TCP connection is a CSV stream (header included), parsed exactly like a CSV file.
+  Connections are read concurrently, but all the operations are funneled through a channel into a single
+  PaymentsEngine, which is owned by one task and processes them one at a time: no locking is needed, and
+  the engine sees a single, totally ordered stream of operations.
+  Sharding by client is not an option here, as disputes, resolves and chargebacks are routed only by transaction id.
+
+  The account table is printed on demand (SIGUSR1 on unix) and the session is returned on graceful shutdown (the shutdown
+  future completing, Ctrl+C for the binary): no more connections are accepted, every open connection stops at the row
+  it is reading, and all the rows read until then are processed before the session is returned.
+*/
+pub async fn serve_tcp(listener: TcpListener, processing_session: ProcessingSession, output_settings: OutputSettings, shutdown: impl Future<Output = ()>) -> Result<ProcessingSession, Box<dyn Error + Send + Sync>> {
+    info!(address = %listener.local_addr()?, "Listening for CSV streams");
+
+    let (engine_commands_sender, engine_commands_receiver) = mpsc::channel(ENGINE_COMMANDS_BUFFER_SIZE);
+    let engine_task = tokio::spawn(run_engine(processing_session, engine_commands_receiver, output_settings));
+
+    #[cfg(unix)]
+    let dump_task = tokio::spawn(forward_dump_signals(engine_commands_sender.clone()));
+
+    let shutdown_token = CancellationToken::new();
+    let mut connections = JoinSet::new();
+    tokio::pin!(shutdown);
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                match accepted {
+                    Ok((stream, peer_address)) => {
+                        connections.spawn(process_connection(stream, format!("tcp://{peer_address}"), engine_commands_sender.clone(), shutdown_token.clone()));
+                    }
+                    Err(error) => error!(%error, "Error while accepting a TCP connection")
+                }
+            }
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            () = &mut shutdown => {
+                info!(open_connections = connections.len(), "Shutting down, the connections still open will be closed");
+                break;
+            }
+        }
+    }
+
+    shutdown_token.cancel();
+    while connections.join_next().await.is_some() {}
+    #[cfg(unix)]
+    dump_task.abort();
+    drop(engine_commands_sender);
+
+    Ok(engine_task.await?)
+}
+
+async fn run_engine(mut processing_session: ProcessingSession, mut engine_commands_receiver: Receiver<EngineCommand>, output_settings: OutputSettings) -> ProcessingSession {
+    while let Some(engine_command) = engine_commands_receiver.recv().await {
+        match engine_command {
+            EngineCommand::Process { operation_request_result, source_name, line } => {
+                processing_session.process_row(&source_name, line, operation_request_result);
+            }
+            EngineCommand::Dump => print_outcome(processing_session.payments_engine.accounts(), &output_settings)
+        }
+    }
+    processing_session
+}
+
+/*
+  On shutdown, the row being read is dropped, while the ones already read have all been sent to the engine.
+*/
+async fn process_connection(stream: TcpStream, source_name: String, engine_commands_sender: Sender<EngineCommand>, shutdown_token: CancellationToken) {
+    info!(source = source_name, "Connection opened");
+    let mut operation_requests = read_operation_requests_from_csv(stream.compat());
+
+    loop {
+        let maybe_row = tokio::select! {
+            maybe_row = operation_requests.next() => maybe_row,
+            () = shutdown_token.cancelled() => None
+        };
+        let Some((line, operation_request_result)) = maybe_row else {
+            break;
+        };
+        let engine_command = EngineCommand::Process { operation_request_result, source_name: source_name.clone(), line };
+        if engine_commands_sender.send(engine_command).await.is_err() {
+            break;
+        }
+    }
+    info!(source = source_name, "Connection closed");
+}
+
+#[cfg(unix)]
+async fn forward_dump_signals(engine_commands_sender: Sender<EngineCommand>) -> Result<(), std::io::Error> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut dump_signal = signal(SignalKind::user_defined1())?;
+    while dump_signal.recv().await.is_some() {
+        if engine_commands_sender.send(EngineCommand::Dump).await.is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod serve_tcp_use_case_test;
@@ -0,0 +1,43 @@
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use crate::domain::Amount;
+use crate::domain::payments_engine::PaymentsEngine;
+use crate::output_printer::OutputSettings;
+use crate::use_case::processing_session::ProcessingSession;
+use crate::use_case::serve_tcp_use_case::serve_tcp;
+
+/*
+  The server closes a connection once all its rows have been sent to the engine, so reading until EOF waits for that.
+*/
+async fn stream_rows(address: std::net::SocketAddr, rows: &str) {
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    stream.write_all(rows.as_bytes()).await.unwrap();
+    stream.shutdown().await.unwrap();
+    stream.read_to_end(&mut vec![]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_rows_streamed_over_two_connections_are_processed_before_shutdown() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+    let server = tokio::spawn(serve_tcp(listener, ProcessingSession::new(PaymentsEngine::new()), OutputSettings::default(), async { shutdown_receiver.await.unwrap_or(()) }));
+
+    stream_rows(address, "type,client,tx,amount\ndeposit,1,1,10\ndeposit,2,2,5\nwithdrawal,1,3,4\n").await;
+    stream_rows(address, "type,client,tx,amount\ndeposit,1,4,1.5\nwithdrawal,2,5,6\ndispute,2,2,\n").await;
+    // A connection still open, in the middle of a row, doesn't hold the shutdown back
+    let mut idle_stream = TcpStream::connect(address).await.unwrap();
+    idle_stream.write_all(b"type,client,tx,amount\ndeposit,3,").await.unwrap();
+
+    shutdown_sender.send(()).unwrap();
+    let (payments_engine, statistics) = server.await.unwrap().unwrap().finish().unwrap();
+
+    assert_eq!(statistics.rows_read, 6);
+    assert_eq!(statistics.rows_accepted, 5);
+    assert_eq!(statistics.rows_rejected, 1);
+    assert_eq!(payments_engine.account(1).unwrap().available_amount(), Amount::new(75, 1));
+    assert_eq!(payments_engine.account(2).unwrap().available_amount(), Amount::ZERO);
+    assert_eq!(payments_engine.account(2).unwrap().held_amount(), Amount::new(5, 0));
+    assert!(payments_engine.account(3).is_none());
+}